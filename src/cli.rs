@@ -0,0 +1,406 @@
+use std::{io::IsTerminal, path::PathBuf};
+
+use anyhow::{bail, Context};
+use clap::{ArgAction, Args, Parser, Subcommand};
+use tracing::instrument;
+use wt::{
+    commands::{
+        adopt, config, describe, doctor, exec, fetch, format_checks, format_description,
+        format_listing, format_next_steps, format_status, format_status_json, format_value, init,
+        init_via_clone, list, lock, new, open_in_editor, populate, porcelain_listing, remove,
+        rename_worktree_branch, root, status, switch, sync, unlock, unshallow, which, Adopt, Clone,
+        Config, ConfigAction, Describe, Exec, Fetch, Init, List, Lock, New, Populate, Remove,
+        RenameBranch, Status, Switch, Sync, SyncStatus, Unlock, Unshallow, Which,
+    },
+    pager::print_paged,
+    tmux, Error,
+};
+
+#[derive(Parser, Debug)]
+#[command(version)]
+#[command(about = "Utility for managing git worktrees")]
+pub struct Cli {
+    #[command(flatten)]
+    pub global_opts: GlobalOptions,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct GlobalOptions {
+    #[arg(short, long)]
+    #[arg(help = "Silences all output")]
+    pub quiet: bool,
+
+    #[arg(short, long, action = ArgAction::Count)]
+    #[arg(help = "Show debug output, or trace output when repeated (overridden by RUST_LOG)")]
+    pub verbose: u8,
+
+    #[arg(short, long)]
+    #[arg(help = "Answer yes to confirmation prompts")]
+    pub yes: bool,
+
+    #[arg(long)]
+    #[arg(help = "Disable colored output (also disabled by setting NO_COLOR)")]
+    pub no_color: bool,
+
+    #[arg(long, value_name = "PATH")]
+    #[arg(help = "Operate on the project containing PATH [default: current directory]")]
+    pub repo: Option<PathBuf>,
+
+    #[arg(long)]
+    #[arg(help = "Never pipe long output of `list` and `status` through $PAGER")]
+    pub no_pager: bool,
+}
+
+impl GlobalOptions {
+    /// Returns the path from which to search for the project's repository
+    pub fn search_path(&self) -> Result<PathBuf, Error> {
+        match self.repo {
+            Some(ref path) => {
+                if !path.exists() {
+                    bail!("repository path does not exist: {}", path.display());
+                }
+                gix::discover(path).with_context(|| {
+                    format!("path isn't inside a git repository: {}", path.display())
+                })?;
+                Ok(path.clone())
+            }
+            None => std::env::current_dir().context("couldn't get current directory"),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    #[command(about = "Create a new worktree project")]
+    #[command(long_about = include_str!("long_help/init.md"))]
+    Init(Init),
+    #[command(about = "Create a new worktree")]
+    #[command(long_about = include_str!("long_help/new.md"))]
+    New(New),
+    #[command(about = "Create a worktree for each of a remote's branches")]
+    #[command(long_about = include_str!("long_help/populate.md"))]
+    Populate(Populate),
+    #[command(about = "Remove the specified worktrees")]
+    #[command(long_about = include_str!("long_help/rm.md"))]
+    #[command(alias = "rm")]
+    Remove(Remove),
+    #[command(about = "List worktrees")]
+    #[command(alias = "ls")]
+    List(List),
+    #[command(about = "Create a worktree project by cloning a repository")]
+    #[command(long_about = include_str!("long_help/clone.md"))]
+    Clone(Clone),
+    #[command(about = "Convert an existing repository into a worktree project")]
+    #[command(long_about = include_str!("long_help/adopt.md"))]
+    Adopt(Adopt),
+    #[command(about = "Read and write settings")]
+    #[command(long_about = include_str!("long_help/config.md"))]
+    Config(Config),
+    #[command(about = "Show everything about a single worktree")]
+    Describe(Describe),
+    #[command(about = "Diagnose problems with the environment and the project")]
+    Doctor,
+    #[command(about = "Run a command in every worktree")]
+    #[command(long_about = include_str!("long_help/exec.md"))]
+    Exec(Exec),
+    #[command(about = "Print the root directory of the project")]
+    Root,
+    #[command(about = "Summarize the state of every worktree")]
+    #[command(long_about = include_str!("long_help/status.md"))]
+    Status(Status),
+    #[command(
+        about = "Fetch from remotes once and report the remote-tracking branches that changed"
+    )]
+    #[command(long_about = include_str!("long_help/fetch.md"))]
+    Fetch(Fetch),
+    #[command(about = "Fetch from remotes and optionally fast-forward every worktree")]
+    #[command(long_about = include_str!("long_help/sync.md"))]
+    Sync(Sync),
+    #[command(about = "Fetch the history that a shallow clone left out")]
+    #[command(long_about = include_str!("long_help/unshallow.md"))]
+    Unshallow(Unshallow),
+    #[command(about = "Pick a worktree interactively and print its path")]
+    #[command(long_about = include_str!("long_help/switch.md"))]
+    Switch(Switch),
+    #[command(about = "Rename the branch checked out in a worktree without moving it")]
+    #[command(long_about = include_str!("long_help/rename_branch.md"))]
+    RenameBranch(RenameBranch),
+    #[command(about = "Print the path of a worktree")]
+    Which(Which),
+    #[command(about = "Lock a worktree so that it can't be removed")]
+    #[command(long_about = include_str!("long_help/lock.md"))]
+    Lock(Lock),
+    #[command(about = "Unlock a locked worktree")]
+    Unlock(Unlock),
+}
+
+#[instrument(skip(cmd))]
+pub fn run(cmd: &Commands, opts: &GlobalOptions) -> Result<(), Error> {
+    match cmd {
+        Commands::Init(args) => {
+            let path = init(args)?;
+            if !opts.quiet {
+                println!("{}", path.display());
+            }
+            Ok(())
+        }
+        Commands::New(args) => {
+            // Fail before creating a worktree that can't be opened the way the user asked
+            if args.tmux && !tmux::in_tmux() {
+                bail!("--tmux was given but wt isn't running inside a tmux session");
+            }
+            let args = New {
+                yes: opts.yes,
+                ..args.clone()
+            };
+            let outcome = new(&args, &opts.search_path()?)?;
+            if !opts.quiet {
+                if outcome.existing {
+                    eprintln!(
+                        "branch '{}' is already checked out, using its existing worktree",
+                        outcome.branch
+                    );
+                }
+                for file in &outcome.skipped_template_files {
+                    eprintln!(
+                        "warning: didn't copy template file '{}' because the worktree already has it",
+                        file.display()
+                    );
+                }
+                for file in &outcome.replaced_files {
+                    eprintln!(
+                        "warning: kept the existing '{}' in place of the checked-out file",
+                        file.display()
+                    );
+                }
+                for pattern in &outcome.unmatched_symlink_patterns {
+                    eprintln!("warning: symlink pattern '{pattern}' didn't match anything");
+                }
+                println!("{}", outcome.path.display());
+                // Hints would end up in the path when it's captured with `$(wt new ...)`, so
+                // they're only shown to someone watching
+                if !outcome.existing && std::io::stdout().is_terminal() {
+                    eprint!("{}", format_next_steps(&outcome));
+                }
+            }
+            if args.tmux {
+                tmux::open_window(args.worktree_name()?, &outcome.path)?;
+            }
+            if let Some(ref editor) = args.editor {
+                open_in_editor(editor, &outcome.path)?;
+            }
+            Ok(())
+        }
+        Commands::Populate(args) => {
+            let outcome = populate(args, &opts.search_path()?)?;
+            if !opts.quiet {
+                for (branch, path) in &outcome.created {
+                    println!("{branch}: {}", path.display());
+                }
+                eprintln!(
+                    "created {} worktrees, skipped {} that already existed",
+                    outcome.created.len(),
+                    outcome.skipped.len()
+                );
+            }
+            Ok(())
+        }
+        Commands::Remove(args) => {
+            let args = Remove {
+                yes: opts.yes,
+                ..args.clone()
+            };
+            let outcome = remove(&args, &opts.search_path()?)?;
+            if !opts.quiet {
+                for warning in &outcome.warnings {
+                    eprintln!("warning: {warning}");
+                }
+                let verb = if args.dry_run {
+                    "would remove"
+                } else {
+                    "removed"
+                };
+                for removed in &outcome.removed {
+                    let stale = if removed.pruned { "stale " } else { "" };
+                    let mut msg = format!("{verb} {stale}worktree '{}'", removed.name);
+                    if let Some(branch) = &removed.deleted_branch {
+                        msg.push_str(format!(" and branch '{branch}'").as_str());
+                    }
+                    if let Some(remote_branch) = &removed.deleted_remote_branch {
+                        msg.push_str(format!(" and remote branch '{remote_branch}'").as_str());
+                    }
+                    eprintln!("{}", msg);
+                }
+                for dir in &outcome.pruned_dirs {
+                    eprintln!("removed empty directory {}", dir.display());
+                }
+            }
+            if args.porcelain {
+                for removed in &outcome.removed {
+                    println!("{}", removed.path.display());
+                }
+            }
+            if !outcome.kept.is_empty() {
+                bail!(
+                    "some worktrees weren't removed because their pre_remove hook failed: {}",
+                    outcome.kept.join(", ")
+                );
+            }
+            Ok(())
+        }
+        Commands::List(args) if args.porcelain => {
+            print_paged(
+                &porcelain_listing(args, &opts.search_path()?)?,
+                !opts.no_pager,
+            )?;
+            Ok(())
+        }
+        Commands::List(args) => {
+            let entries = list(args, &opts.search_path()?)?;
+            print_paged(&format_listing(&entries, args)?, !opts.no_pager)?;
+            Ok(())
+        }
+        Commands::Exec(args) => {
+            exec(args, &opts.search_path()?)?;
+            Ok(())
+        }
+        Commands::Root => {
+            let path = root(&opts.search_path()?)?;
+            println!("{}", path.display());
+            Ok(())
+        }
+        Commands::Status(args) => {
+            let statuses = status(args, &opts.search_path()?)?;
+            if args.json {
+                println!("{}", format_status_json(&statuses)?);
+            } else {
+                print_paged(&format_status(&statuses), !opts.no_pager)?;
+            }
+            if statuses.iter().any(|s| s.dirty) {
+                bail!("some worktrees have uncommitted changes");
+            }
+            Ok(())
+        }
+        Commands::Fetch(args) => {
+            let changes = fetch(args, &opts.search_path()?)?;
+            if !opts.quiet {
+                for (name, change) in &changes {
+                    println!("{change}: {name}");
+                }
+                if changes.is_empty() {
+                    eprintln!("remote-tracking branches are already up to date");
+                }
+            }
+            Ok(())
+        }
+        Commands::Sync(args) => {
+            let statuses = sync(args, &opts.search_path()?)?;
+            if !opts.quiet {
+                for (name, status) in statuses {
+                    if status == SyncStatus::SkippedDirty {
+                        eprintln!("warning: skipped '{name}' because it has uncommitted changes");
+                    }
+                    println!("{name}: {status}");
+                }
+            }
+            Ok(())
+        }
+        Commands::Unshallow(args) => {
+            let fetched = unshallow(args, &opts.search_path()?)?;
+            if !opts.quiet && !fetched {
+                eprintln!("the project already has its full history");
+            }
+            Ok(())
+        }
+        Commands::Switch(args) => {
+            let path = switch(args, &opts.search_path()?)?;
+            println!("{}", path.display());
+            Ok(())
+        }
+        Commands::RenameBranch(args) => {
+            let old = rename_worktree_branch(args, &opts.search_path()?)?;
+            if !opts.quiet {
+                eprintln!(
+                    "renamed branch '{old}' to '{}' in worktree '{}'",
+                    args.new_branch, args.name
+                );
+            }
+            Ok(())
+        }
+        Commands::Which(args) => {
+            let path = which(args, &opts.search_path()?)?;
+            println!("{}", path.display());
+            Ok(())
+        }
+        Commands::Lock(args) => {
+            lock(args, &opts.search_path()?)?;
+            if !opts.quiet {
+                eprintln!("locked worktree '{}'", args.name);
+            }
+            Ok(())
+        }
+        Commands::Unlock(args) => {
+            let reason = unlock(args, &opts.search_path()?)?;
+            if !opts.quiet {
+                match reason {
+                    Some(reason) => {
+                        eprintln!("unlocked worktree '{}' (was locked: {reason})", args.name)
+                    }
+                    None => eprintln!("unlocked worktree '{}'", args.name),
+                }
+            }
+            Ok(())
+        }
+        Commands::Adopt(args) => {
+            let path = adopt(args)?;
+            if !opts.quiet {
+                println!("{}", path.display());
+            }
+            Ok(())
+        }
+        Commands::Config(args) => {
+            let settings = config(args, &opts.search_path()?)?;
+            for (key, value) in settings {
+                match args.action {
+                    ConfigAction::List => println!("{key} = {value}"),
+                    _ => println!("{}", format_value(&value)),
+                }
+            }
+            Ok(())
+        }
+        Commands::Describe(args) => {
+            let description = describe(args, &opts.search_path()?)?;
+            print!("{}", format_description(&description));
+            Ok(())
+        }
+        Commands::Doctor => {
+            let checks = doctor(&opts.search_path()?);
+            print!("{}", format_checks(&checks));
+            if checks.iter().any(|c| !c.passed) {
+                bail!("some checks failed");
+            }
+            Ok(())
+        }
+        Commands::Clone(args) => {
+            let path = init_via_clone(args, opts.quiet)?;
+            if !opts.quiet {
+                println!("{}", path.display());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_cli() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert()
+    }
+}
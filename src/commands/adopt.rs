@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use clap::{Args, ValueHint};
+use tracing::{debug, instrument};
+
+use crate::{
+    error::WtError,
+    git::{default_branch_name, is_dirty, repair_worktrees, worktree_path},
+    util::traceable_path,
+    Error,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Adopt {
+    #[arg(value_name = "REPO", value_hint = ValueHint::DirPath)]
+    #[arg(help = "The repository to convert into a worktree project [default: current directory]")]
+    pub path: Option<PathBuf>,
+}
+
+/// Converts an ordinary clone into a worktree project
+///
+/// The repository's directory becomes the project root and the checkout is moved into a
+/// subdirectory named after the default branch, which is the same layout
+/// [init_via_clone](crate::commands::init_via_clone) produces. Returns the new path of the
+/// checkout.
+#[instrument]
+pub fn adopt(args: &Adopt) -> Result<PathBuf, WtError> {
+    let current_dir = std::env::current_dir().context("couldn't get current directory")?;
+    let start = match args.path {
+        Some(ref p) => current_dir.join(p),
+        None => current_dir,
+    };
+    let repo = gix::discover(&start).context("couldn't find a repository to adopt")?;
+    if repo.is_bare() {
+        return Err(anyhow!("can't adopt a bare repository").into());
+    }
+    let repo_path = worktree_path(&repo)?.to_path_buf();
+    let main_repo = repo.main_repo().context("couldn't find main worktree")?;
+    if main_repo.work_dir() != Some(repo_path.as_path()) {
+        return Err(anyhow!(
+            "can only adopt a repository from its main worktree: {}",
+            repo_path.display()
+        )
+        .into());
+    }
+    let branch = default_branch_name(Some(&repo))?;
+    if is_dirty(&repo_path)? {
+        return Err(anyhow!(
+            "repository has uncommitted changes, commit or stash them before adopting: {}",
+            repo_path.display()
+        )
+        .into());
+    }
+    let new_path = move_into_subdir(&repo_path, &branch)?;
+    repair_worktrees(&new_path).context("couldn't repair links to existing worktrees")?;
+    Ok(new_path)
+}
+
+/// Moves the contents of `repo_path` into `repo_path/<subdir>`, returning the new path
+///
+/// If the checkout can't be moved into the subdirectory it's moved back to `repo_path`.
+fn move_into_subdir(repo_path: &Path, subdir: &str) -> Result<PathBuf, Error> {
+    let parent = repo_path
+        .parent()
+        .context("repository has no parent directory")?;
+    let name = repo_path
+        .file_name()
+        .context("repository path has no file name")?;
+    // The checkout has to be moved out of the way before it can be moved into a directory with
+    // the same name as the one it currently occupies
+    let temp_path = parent.join(format!(".{}.wt-adopt", name.to_string_lossy()));
+    if temp_path.exists() {
+        bail!(
+            "can't move repository, path already exists: {}",
+            temp_path.display()
+        );
+    }
+    debug!(
+        from = traceable_path(repo_path),
+        to = traceable_path(&temp_path),
+        "moving checkout out of the way"
+    );
+    std::fs::rename(repo_path, &temp_path)
+        .with_context(|| format!("couldn't move repository: {}", repo_path.display()))?;
+    let new_path = repo_path.join(subdir);
+    if let Err(err) = move_from_temp(&temp_path, repo_path, &new_path) {
+        debug!(
+            from = traceable_path(&temp_path),
+            to = traceable_path(repo_path),
+            "moving checkout back"
+        );
+        // Only directories created by `move_from_temp` can be in the way
+        if repo_path.exists() {
+            if let Err(cleanup_err) = std::fs::remove_dir_all(repo_path) {
+                return Err(err.context(format!(
+                    "couldn't clean up project root ({cleanup_err}), the repository was left at {}",
+                    temp_path.display()
+                )));
+            }
+        }
+        if let Err(restore_err) = std::fs::rename(&temp_path, repo_path) {
+            return Err(err.context(format!(
+                "couldn't move repository back ({restore_err}), it was left at {}",
+                temp_path.display()
+            )));
+        }
+        return Err(err);
+    }
+    Ok(new_path)
+}
+
+/// Creates the project root at `repo_path` and moves the checkout from `temp_path` to `new_path`
+fn move_from_temp(temp_path: &Path, repo_path: &Path, new_path: &Path) -> Result<(), Error> {
+    std::fs::create_dir(repo_path)
+        .with_context(|| format!("couldn't create project root: {}", repo_path.display()))?;
+    if let Some(parent) = new_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("couldn't create directory: {}", parent.display()))?;
+    }
+    std::fs::rename(temp_path, new_path)
+        .with_context(|| format!("couldn't move repository: {}", temp_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::git::{create_initial_commit, current_branch_name, git_in};
+
+    fn ordinary_repo(parent: &Path) -> PathBuf {
+        let repo_dir = parent.join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        gix::init(&repo_dir).unwrap();
+        git_in(&repo_dir, &["config", "init.defaultBranch", "trunk"]);
+        crate::git::set_unborn_branch(&repo_dir, "trunk").unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+        repo_dir
+    }
+
+    #[test]
+    fn adopts_ordinary_clone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = ordinary_repo(temp_dir.path());
+        let new_path = adopt(&Adopt {
+            path: Some(repo_dir.clone()),
+        })
+        .unwrap();
+        assert_eq!(new_path, repo_dir.join("trunk"));
+        let repo = gix::open(&new_path).unwrap();
+        assert_eq!(current_branch_name(&repo).unwrap(), "trunk");
+        assert!(!repo_dir.join(".git").exists());
+    }
+
+    #[test]
+    fn names_checkout_after_default_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = ordinary_repo(temp_dir.path());
+        git_in(&repo_dir, &["switch", "-q", "-c", "feature"]);
+        let new_path = adopt(&Adopt {
+            path: Some(repo_dir.clone()),
+        })
+        .unwrap();
+        assert_eq!(new_path, repo_dir.join("trunk"));
+        let repo = gix::open(&new_path).unwrap();
+        assert_eq!(current_branch_name(&repo).unwrap(), "feature");
+    }
+
+    #[test]
+    fn moves_checkout_back_when_move_fails() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = ordinary_repo(temp_dir.path());
+        // Paths can't contain a NUL byte, so the second move always fails
+        assert!(move_into_subdir(&repo_dir, "bad\0name").is_err());
+        assert!(repo_dir.join(".git").exists());
+        assert!(!temp_dir.path().join(".repo_name.wt-adopt").exists());
+    }
+
+    #[test]
+    fn refuses_dirty_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = ordinary_repo(temp_dir.path());
+        std::fs::write(repo_dir.join("file.txt"), "contents").unwrap();
+        let res = adopt(&Adopt {
+            path: Some(repo_dir.clone()),
+        });
+        assert!(res.is_err());
+        assert!(repo_dir.join(".git").exists());
+    }
+}
@@ -1,82 +1,326 @@
-use std::path::PathBuf;
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use clap::{Args, ValueHint};
 use gix::Repository;
+use tracing::debug;
 
-use crate::{git::clone_repo, Error};
+use crate::{
+    commands::init::resolve_parent_path,
+    config::{default_layout, Layout},
+    error::WtError,
+    git::{
+        branch_from_ref, check_remote_reachable, clone_repo_with, credentials_hint,
+        is_auth_failure, new_worktree, set_project_layout, CloneOptions,
+    },
+    prompt::is_interactive,
+    util::dir_is_occupied,
+    Error,
+};
 
-#[derive(Args, Debug, Clone)]
+#[derive(Args, Debug, Clone, Default)]
 pub struct Clone {
     /// The URL or path to the repository to clone
     #[arg(value_name = "REPO")]
     pub repo: String,
 
     #[arg(short, long, value_name = "PATH", value_hint = ValueHint::DirPath)]
-    #[arg(help = "The path under which to create the project [default: current directory]")]
+    #[arg(
+        help = "The path under which to create the project [default: WT_PROJECTS_DIR, projects.dir, or the current directory]"
+    )]
     pub path: Option<PathBuf>,
 
     #[arg(short, long, value_name = "NAME")]
     #[arg(help = "The name of the project [default: repository name]")]
     pub name: Option<String>,
+
+    #[arg(short, long)]
+    #[arg(help = "Clone even if the project directory already exists")]
+    pub force: bool,
+
+    #[arg(long, value_enum, value_name = "LAYOUT")]
+    #[arg(help = "Where to put the project's worktrees [default: projects.layout, or flat]")]
+    pub layout: Option<Layout>,
+
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    #[arg(help = "Only clone the last N commits of history, see `wt unshallow` to get the rest")]
+    pub depth: Option<u32>,
+
+    #[arg(long)]
+    #[arg(help = "Only clone the history of the remote's default branch")]
+    pub single_branch: bool,
+
+    #[arg(long, value_name = "NAME")]
+    #[arg(help = "The name to give the remote [default: origin]")]
+    pub remote: Option<String>,
+
+    #[arg(long, conflicts_with = "single_branch")]
+    #[arg(help = "Keep a bare mirror of every ref in the remote at the root of the project")]
+    pub mirror: bool,
 }
 
 /// Create a worktrees project by cloning a repository
-pub fn init_via_clone(args: &Clone) -> Result<PathBuf, Error> {
+///
+/// Unless `quiet` is set git is attached to the terminal, if there is one, so that it can show its
+/// progress and ask for credentials.
+pub fn init_via_clone(args: &Clone, quiet: bool) -> Result<PathBuf, WtError> {
     let current_dir = std::env::current_dir().context("couldn't get current directory")?;
-    let path_to_clone_under = args.path.as_ref().map(|p| {
-        if p.is_relative() {
-            current_dir.join(p)
-        } else {
-            p.clone()
-        }
-    });
-    if let Some(ref path) = path_to_clone_under {
-        if !path.exists() {
-            return Err(anyhow!("path does not exist: {}", path.display()));
-        }
+    let path_to_clone_under = resolve_parent_path(args.path.as_deref(), &current_dir)?;
+    let attached = !quiet && is_interactive() && std::io::stderr().is_terminal();
+
+    validate_repo(&args.repo, &current_dir, attached)?;
+    if args.mirror {
+        return Ok(init_via_mirror(args, &path_to_clone_under, attached)?);
     }
 
     // Need to determine the name of the repository so we can name the parent directory of
     // all the worktrees
     let temp_dir = tempfile::tempdir().context("failed to create tempdir")?;
-    let temp_repo_path = clone_repo(&args.repo, temp_dir.path(), None::<&str>)?;
-    let repo_name = temp_repo_path
-        .file_name()
-        .ok_or(anyhow!("repo path had no file name"))?;
+    let clone_opts = CloneOptions {
+        depth: args.depth,
+        single_branch: args.single_branch,
+        mirror: false,
+        origin: args.remote.clone(),
+        branch: None,
+        attached,
+    };
+    let temp_repo_path = clone_repo_with(&args.repo, temp_dir.path(), None::<&str>, &clone_opts)?;
+    let repo_name = match args.name {
+        Some(ref name) => name.clone(),
+        None => match repo_name_from_url(&args.repo) {
+            Some(name) => name,
+            None => temp_repo_path
+                .file_name()
+                .ok_or(anyhow!("repo path had no file name"))?
+                .to_string_lossy()
+                .to_string(),
+        },
+    };
+    // The branch the throwaway clone checked out is the one git itself chose
     let temp_repo = gix::open(&temp_repo_path).context("failed to open temp checkout")?;
-    let default_branch = get_fresh_clone_branch_name(&temp_repo)
-        .context("couldn't determine repo default branch")?;
-    let project_path = if let Some(ref p) = path_to_clone_under {
-        p.join(repo_name)
-    } else {
-        current_dir.join(repo_name)
+    let default_branch = get_fresh_clone_branch_name(&temp_repo, &args.repo)?;
+    let project_path = path_to_clone_under.join(&repo_name);
+
+    if !args.force && dir_is_occupied(&project_path)? {
+        return Err(anyhow!(
+            "project directory already exists and isn't empty: {} (use --force to clone anyway)",
+            project_path.display()
+        )
+        .into());
+    }
+    let layout = match args.layout {
+        Some(layout) => layout,
+        None => default_layout()?,
+    };
+    let worktrees_dir = layout.worktrees_dir(&project_path);
+    std::fs::create_dir_all(&worktrees_dir).context("failed to create project directory")?;
+    // The remote's HEAD could have moved since the throwaway clone, and the main worktree has to
+    // be on the branch it's named after
+    let clone_opts = CloneOptions {
+        branch: Some(default_branch.clone()),
+        ..clone_opts
     };
+    clone_repo_with(
+        &args.repo,
+        &worktrees_dir,
+        Some(&default_branch),
+        &clone_opts,
+    )
+    .context("failed to clone repository")?;
+    let main_wt_path = worktrees_dir.join(default_branch);
+    set_project_layout(&main_wt_path, layout)?;
+    Ok(main_wt_path)
+}
 
-    std::fs::create_dir_all(&project_path).context("failed to create project directory")?;
-    clone_repo(&args.repo, &project_path, Some(&default_branch))
+/// Creates a project whose main repository is a bare mirror of the remote, laid out like
+/// `wt init --bare` with a worktree for the remote's default branch
+///
+/// A mirror has the remote's branches as its own, so unlike a regular clone this doesn't need a
+/// throwaway clone to find the default branch, but the name of the project has to come from the
+/// URL.
+fn init_via_mirror(
+    args: &Clone,
+    path_to_clone_under: &Path,
+    attached: bool,
+) -> Result<PathBuf, Error> {
+    let repo_name = match args.name {
+        Some(ref name) => name.clone(),
+        None => repo_name_from_url(&args.repo).with_context(|| {
+            format!(
+                "couldn't determine the project name from '{}', pass it with --name",
+                args.repo
+            )
+        })?,
+    };
+    let project_path = path_to_clone_under.join(repo_name);
+    if !args.force && dir_is_occupied(&project_path)? {
+        bail!(
+            "project directory already exists and isn't empty: {} (use --force to clone anyway)",
+            project_path.display()
+        );
+    }
+    let clone_opts = CloneOptions {
+        depth: args.depth,
+        mirror: true,
+        origin: args.remote.clone(),
+        attached,
+        ..Default::default()
+    };
+    let git_dir = clone_repo_with(&args.repo, &project_path, Some(".git"), &clone_opts)
         .context("failed to clone repository")?;
-    Ok(project_path.join(default_branch))
+    let mirror = gix::open(&git_dir).context("failed to open mirror")?;
+    let default_branch = get_fresh_clone_branch_name(&mirror, &args.repo)?;
+    let layout = match args.layout {
+        Some(layout) => layout,
+        None => default_layout()?,
+    };
+    set_project_layout(&git_dir, layout)?;
+    let main_wt_path = layout.worktrees_dir(&project_path).join(&default_branch);
+    new_worktree(&git_dir, &main_wt_path, &default_branch)
+        .context("couldn't create worktree for default branch")?;
+    Ok(main_wt_path)
+}
+
+/// How long to wait for a remote repository to respond before giving up on cloning it
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks that the repository to clone exists before spending time cloning it
+///
+/// A remote that needs credentials can only be checked by cloning it when git is `attached` to the
+/// terminal to ask for them.
+fn validate_repo(repo: &str, current_dir: &Path, attached: bool) -> Result<(), Error> {
+    if is_local_path(repo) {
+        let path = current_dir.join(repo);
+        if !path.exists() {
+            bail!("can't clone repository, path not found: {}", path.display());
+        }
+        if gix::open(&path).is_err() {
+            bail!(
+                "can't clone repository, path isn't a git repository: {}",
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+    match check_remote_reachable(repo, REMOTE_TIMEOUT) {
+        Ok(()) => Ok(()),
+        Err(err) if is_auth_failure(&err) => {
+            if attached {
+                debug!("remote needs credentials, leaving git to ask for them while cloning");
+                return Ok(());
+            }
+            Err(anyhow::Error::from(err).context(credentials_hint(repo)))
+        }
+        Err(err) => Err(anyhow::Error::from(err).context(format!(
+            "can't clone repository, remote unreachable: {repo}"
+        ))),
+    }
+}
+
+/// Returns whether git would treat the repository argument as a local path rather than a URL
+///
+/// git treats anything containing `://` as a URL, and `host:path` as an scp-style URL as long as
+/// there's no slash before the colon.
+fn is_local_path(repo: &str) -> bool {
+    if repo.contains("://") {
+        return false;
+    }
+    match repo.find(':') {
+        Some(colon) => repo[..colon].contains('/'),
+        None => true,
+    }
+}
+
+/// Extracts the name of a repository from its URL or path, without any trailing `.git`
+///
+/// This handles `https://host/org/repo.git`, scp-style `git@host:org/repo.git`, and local paths.
+pub fn repo_name_from_url(url: &str) -> Option<String> {
+    let trimmed = url.trim().trim_end_matches('/');
+    let last = trimmed.rsplit(['/', ':']).next()?;
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+    Some(name.to_string())
 }
 
 /// Gets the name of the branch checked out in a fresh clone
-fn get_fresh_clone_branch_name(repo: &Repository) -> Result<String, Error> {
-    let branch = repo
-        .branch_names()
-        .into_iter()
-        .next()
-        .context("repo had no branches")?;
-    Ok(branch.to_string())
+///
+/// `git clone` checks out the branch that the remote's HEAD points to, whatever the remote is
+/// called, so this is the remote's default branch rather than whichever branch happens to be
+/// listed first.
+fn get_fresh_clone_branch_name(repo: &Repository, url: &str) -> Result<String, Error> {
+    let head = repo.head().context("couldn't read HEAD of fresh clone")?;
+    if head.is_unborn() {
+        bail!(
+            "can't create a project from {url} because it has no commits on its default branch, \
+             clone it with `git clone` or push a first commit to it"
+        );
+    }
+    let branch_ref = head
+        .referent_name()
+        .context("fresh clone has a detached HEAD")?;
+    branch_from_ref(branch_ref).context("couldn't determine repo default branch")
 }
 
 #[cfg(test)]
 mod test {
-    use std::process::Command;
-
-    use crate::git::create_initial_commit;
+    use crate::{
+        commands::new::{new, New},
+        git::{
+            create_initial_commit, current_branch_name, get_main_worktree, git_in, project_root,
+            upstream_branch,
+        },
+    };
 
     use super::*;
 
+    #[test]
+    fn derives_repo_names() {
+        for url in [
+            "https://github.com/org/repo.git",
+            "https://github.com/org/repo",
+            "https://github.com/org/repo/",
+            "git@github.com:org/repo.git",
+            "git@github.com:repo.git",
+            "ssh://git@github.com/org/repo.git",
+            "file:///srv/git/repo.git",
+            "/srv/git/repo",
+            "../repo.git",
+        ] {
+            assert_eq!(repo_name_from_url(url).as_deref(), Some("repo"), "{url}");
+        }
+        assert_eq!(repo_name_from_url(".."), None);
+        assert_eq!(repo_name_from_url("https://github.com/.git"), None);
+    }
+
+    #[test]
+    fn recognizes_local_paths() {
+        assert!(is_local_path("/srv/git/repo"));
+        assert!(is_local_path("../repo"));
+        assert!(is_local_path("./dir:with:colons"));
+        assert!(!is_local_path("git@github.com:org/repo.git"));
+        assert!(!is_local_path("https://github.com/org/repo.git"));
+        assert!(!is_local_path("file:///srv/git/repo"));
+    }
+
+    #[test]
+    fn validates_repo_before_cloning() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let err = validate_repo("missing", temp_dir.path(), false).unwrap_err();
+        assert!(err.to_string().contains("path not found"));
+        std::fs::create_dir(temp_dir.path().join("not_a_repo")).unwrap();
+        let err = validate_repo("not_a_repo", temp_dir.path(), false).unwrap_err();
+        assert!(err.to_string().contains("isn't a git repository"));
+        let url = format!("file://{}", temp_dir.path().join("missing").display());
+        let err = validate_repo(&url, temp_dir.path(), false).unwrap_err();
+        assert!(err.to_string().contains("remote unreachable"));
+    }
+
     #[test]
     fn does_init_via_clone() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -89,23 +333,20 @@ mod test {
         assert!(repo_dir.join(".git").exists());
 
         // Get the default branch on this system
-        let output = Command::new("git")
-            .arg("-C")
-            .arg(&repo_dir)
-            .arg("branch")
-            .arg("--show-current")
-            .output()
-            .unwrap();
-        let default_branch = String::from_utf8(output.stdout).unwrap().trim().to_string();
+        let default_branch = git_in(&repo_dir, &["branch", "--show-current"]);
 
         // Clone the repo
         let clone_dir = temp_dir.path().join("clone_dir");
         std::fs::create_dir_all(&clone_dir).unwrap();
-        let project_path = init_via_clone(&Clone {
-            repo: repo_dir.to_string_lossy().to_string(),
-            path: Some(clone_dir.clone()),
-            name: None,
-        })
+        let project_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                name: None,
+                ..Default::default()
+            },
+            true,
+        )
         .unwrap();
         assert_eq!(
             project_path,
@@ -113,4 +354,228 @@ mod test {
         );
         assert!(project_path.exists());
     }
+
+    #[test]
+    fn uses_remote_default_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+        // Both branches sort before the default branch
+        for args in [
+            &["branch", "-M", "trunk"][..],
+            &["branch", "alpha"],
+            &["branch", "beta"],
+        ] {
+            git_in(&repo_dir, args);
+        }
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let project_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(project_path, clone_dir.join("repo_name").join("trunk"));
+    }
+
+    #[test]
+    fn checks_out_remote_head_among_many_branches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+        for branch in ["aardvark", "develop", "main-old", "zebra"] {
+            git_in(&repo_dir, &["branch", branch]);
+        }
+        git_in(&repo_dir, &["switch", "-q", "develop"]);
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let project_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(project_path, clone_dir.join("repo_name").join("develop"));
+        let main_wt = gix::open(&project_path).unwrap();
+        assert_eq!(current_branch_name(&main_wt).unwrap(), "develop");
+    }
+
+    #[test]
+    fn explains_empty_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let err = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("has no commits"), "{err}");
+        assert!(!clone_dir.join("repo_name").exists());
+    }
+
+    #[test]
+    fn clones_with_custom_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let project_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                name: Some("custom".into()),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(project_path.parent().unwrap(), clone_dir.join("custom"));
+        assert!(project_path.exists());
+        assert!(!clone_dir.join("repo_name").exists());
+    }
+
+    #[test]
+    fn clones_with_grouped_layout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let project_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                layout: Some(Layout::Grouped),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let worktrees_dir = clone_dir.join("repo_name").join("worktrees");
+        assert_eq!(project_path.parent().unwrap(), worktrees_dir);
+        let main_wt = gix::open(&project_path).unwrap();
+        assert_eq!(project_root(&main_wt).unwrap(), clone_dir.join("repo_name"));
+    }
+
+    #[test]
+    fn clones_with_custom_remote_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+        let default_branch = current_branch_name(&gix::open(&repo_dir).unwrap()).unwrap();
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                remote: Some("upstream".into()),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            main_wt_path,
+            clone_dir.join("repo_name").join(&default_branch)
+        );
+        assert_eq!(git_in(&main_wt_path, &["remote"]), "upstream");
+        let upstream = upstream_branch(&main_wt_path).unwrap();
+        assert_eq!(upstream, Some(format!("upstream/{default_branch}")));
+    }
+
+    #[test]
+    fn clones_mirror() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+        git_in(&repo_dir, &["branch", "other"]);
+        let default_branch = current_branch_name(&gix::open(&repo_dir).unwrap()).unwrap();
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: repo_dir.to_string_lossy().to_string(),
+                path: Some(clone_dir.clone()),
+                mirror: true,
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let project_dir = clone_dir.join("repo_name");
+        assert_eq!(main_wt_path, project_dir.join(&default_branch));
+        let main_wt = get_main_worktree(&main_wt_path).unwrap();
+        assert!(main_wt.is_bare());
+        assert_eq!(project_root(&main_wt).unwrap(), project_dir);
+
+        // The remote's other branches are already local branches to check out
+        let outcome = new(
+            &New {
+                name: Some("other".into()),
+                branch_name: Some("other".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.path, project_dir.join("other"));
+    }
+
+    #[test]
+    fn refuses_occupied_project_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let _repo = gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        let project_dir = clone_dir.join("repo_name");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("unrelated.txt"), "contents").unwrap();
+        let mut args = Clone {
+            repo: repo_dir.to_string_lossy().to_string(),
+            path: Some(clone_dir.clone()),
+            ..Default::default()
+        };
+        assert!(init_via_clone(&args, true).is_err());
+        args.force = true;
+        assert!(init_via_clone(&args, true).unwrap().exists());
+    }
 }
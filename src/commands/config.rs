@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use clap::{Args, Subcommand};
+use itertools::Itertools;
+use toml::{Table, Value};
+use tracing::instrument;
+
+use crate::{
+    config::{
+        config_path, get_value, load_table, parse_table, read_table, set_value, user_config_path,
+        write_table, KEYS,
+    },
+    error::WtError,
+    git::get_main_worktree,
+    util::traceable_path,
+    Error,
+};
+
+#[derive(Args, Debug, Clone)]
+pub struct Config {
+    #[arg(long, global = true)]
+    #[arg(help = "Use the user's configuration file instead of the project's")]
+    pub user: bool,
+
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigAction {
+    #[command(about = "Print the value of a setting")]
+    Get {
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+    #[command(about = "Change the value of a setting")]
+    Set {
+        #[arg(value_name = "KEY")]
+        key: String,
+        #[arg(value_name = "VALUE")]
+        value: String,
+    },
+    #[command(about = "Print every setting that has a value")]
+    List,
+}
+
+/// Reads or writes settings, returning the settings that were read as `(key, value)` pairs
+///
+/// Reading without `--user` reports the settings in effect for the project containing
+/// `repo_path`, i.e. the user's settings overridden by the project's.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn config(args: &Config, repo_path: &Path) -> Result<Vec<(String, Value)>, WtError> {
+    match args.action {
+        ConfigAction::Get { ref key } => {
+            check_key(key)?;
+            let table = read_settings(args.user, repo_path)?;
+            Ok(get_value(&table, key)
+                .map(|value| vec![(key.clone(), value.clone())])
+                .unwrap_or_default())
+        }
+        ConfigAction::Set { ref key, ref value } => {
+            check_key(key)?;
+            let path = settings_path(args.user, repo_path)?;
+            let mut table = read_table(&path)?;
+            // Values are parsed as TOML so that non-string settings can be set, but most settings
+            // are strings that nobody wants to have to quote
+            let parsed = parse_value(value);
+            set_value(&mut table, key, parsed.clone())?;
+            if parse_table(table.clone()).is_err() && !parsed.is_str() {
+                set_value(&mut table, key, Value::from(value.as_str()))?;
+            }
+            parse_table(table.clone()).with_context(|| format!("invalid value for {key}"))?;
+            write_table(&path, &table)?;
+            Ok(Vec::new())
+        }
+        ConfigAction::List => {
+            let table = read_settings(args.user, repo_path)?;
+            Ok(KEYS
+                .iter()
+                .filter_map(|key| get_value(&table, key).map(|v| (key.to_string(), v.clone())))
+                .collect())
+        }
+    }
+}
+
+/// Formats a setting's value for display, leaving strings unquoted
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn check_key(key: &str) -> Result<(), Error> {
+    if !KEYS.contains(&key) {
+        bail!(
+            "unknown config key '{key}', known keys:\n{}",
+            KEYS.iter().join("\n")
+        );
+    }
+    Ok(())
+}
+
+fn parse_value(value: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::from(value))
+}
+
+fn settings_path(user: bool, repo_path: &Path) -> Result<PathBuf, Error> {
+    if user {
+        return user_config_path().context("couldn't determine location of user config file");
+    }
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    config_path(&main_wt)
+}
+
+fn read_settings(user: bool, repo_path: &Path) -> Result<Table, Error> {
+    if user {
+        return read_table(&settings_path(true, repo_path)?);
+    }
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    load_table(&main_wt)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::init::{init, Init},
+        config::CONFIG_FILE_NAME,
+    };
+
+    #[test]
+    fn sets_and_gets_project_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let run = |action: ConfigAction| {
+            config(
+                &Config {
+                    user: false,
+                    action,
+                },
+                &main_wt_path,
+            )
+        };
+        run(ConfigAction::Set {
+            key: "new.branch_prefix".into(),
+            value: "alice/".into(),
+        })
+        .unwrap();
+        assert!(temp_dir
+            .path()
+            .join("test_proj")
+            .join(CONFIG_FILE_NAME)
+            .exists());
+        let got = run(ConfigAction::Get {
+            key: "new.branch_prefix".into(),
+        })
+        .unwrap();
+        assert_eq!(got[0].1, Value::from("alice/"));
+
+        // Unknown keys and values that don't fit the schema are rejected
+        assert!(run(ConfigAction::Set {
+            key: "new.prefix".into(),
+            value: "alice/".into(),
+        })
+        .is_err());
+        assert!(run(ConfigAction::Set {
+            key: "new.dir_names".into(),
+            value: "sideways".into(),
+        })
+        .is_err());
+    }
+}
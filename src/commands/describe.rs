@@ -0,0 +1,206 @@
+use std::{path::Path, time::SystemTime};
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use itertools::Itertools;
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::{
+    commands::{lock::describe_reason, status::worktree_status, WorktreeStatus},
+    error::WtError,
+    git::{
+        current_worktree, get_worktrees, head_commit, is_current_worktree_alias,
+        main_worktree_info, HeadCommit, ProjectRepos, WorktreeInfo,
+    },
+    util::{format_age, traceable_path},
+    Error,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Describe {
+    #[arg(value_name = "WT_NAME")]
+    pub name: String,
+}
+
+/// Everything there is to know about a single worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorktreeDescription {
+    #[serde(flatten)]
+    pub status: WorktreeStatus,
+    /// The commit HEAD points to, if HEAD isn't unborn
+    pub head: Option<HeadCommit>,
+}
+
+/// Describes the named worktree in the project containing `repo_path`
+///
+/// The name `.` or `@` describes the worktree containing `repo_path`.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn describe(args: &Describe, repo_path: &Path) -> Result<WorktreeDescription, WtError> {
+    let repos = ProjectRepos::discover(repo_path).context("couldn't get main worktree")?;
+    let mut worktrees = get_worktrees(&repos.main).context("couldn't get list of worktrees")?;
+    if is_current_worktree_alias(&args.name) {
+        worktrees.extend(main_worktree_info(&repos.main)?);
+        return Ok(describe_worktree(
+            current_worktree(&repos, &worktrees)?.clone(),
+        )?);
+    }
+    let Some(info) = worktrees.iter().find(|wt| wt.name == args.name) else {
+        return Err(anyhow!(
+            "no worktree named '{}', known worktrees:\n{}",
+            args.name,
+            worktrees.iter().map(|wt| &wt.name).sorted().join("\n")
+        )
+        .into());
+    };
+    Ok(describe_worktree(info.clone())?)
+}
+
+fn describe_worktree(info: WorktreeInfo) -> Result<WorktreeDescription, Error> {
+    let head = head_commit(&info.path)
+        .with_context(|| format!("couldn't read HEAD of worktree '{}'", info.name))?;
+    Ok(WorktreeDescription {
+        status: worktree_status(info)?,
+        head,
+    })
+}
+
+/// Formats the description as one labeled field per line
+pub fn format_description(description: &WorktreeDescription) -> String {
+    let status = &description.status;
+    let info = &status.info;
+    let head = match description.head {
+        Some(ref head) => format!("{} {}", head.sha, head.subject),
+        None => "(unborn)".to_string(),
+    };
+    let upstream = match (&status.upstream, status.ahead, status.behind) {
+        (Some(upstream), Some(ahead), Some(behind)) => {
+            format!("{upstream} (ahead {ahead}, behind {behind})")
+        }
+        _ => "none".to_string(),
+    };
+    let locked = if info.locked {
+        format!("yes{}", describe_reason(&info.lock_reason))
+    } else {
+        "no".to_string()
+    };
+    let created = match info.created_at {
+        Some(ref created_at) => match format_age(created_at, SystemTime::now()) {
+            Some(age) => format!("{created_at} ({age})"),
+            None => created_at.clone(),
+        },
+        None => "unknown".to_string(),
+    };
+    let fields = [
+        ("name", info.name.clone()),
+        ("path", info.path.display().to_string()),
+        (
+            "branch",
+            info.branch.as_deref().unwrap_or("(detached)").to_string(),
+        ),
+        ("head", head),
+        ("upstream", upstream),
+        (
+            "status",
+            if status.dirty { "dirty" } else { "clean" }.to_string(),
+        ),
+        ("locked", locked),
+        ("created", created),
+    ];
+    fields
+        .into_iter()
+        .map(|(label, value)| format!("{:9} {value}\n", format!("{label}:")))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{
+            init::{init, Init},
+            new::{new, New},
+        },
+        git::lock_worktree,
+    };
+
+    #[test]
+    fn describes_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        std::fs::write(outcome.path.join("scratch.txt"), "").unwrap();
+        lock_worktree(&main_wt_path, &outcome.path, Some("usb drive")).unwrap();
+        let description = describe(
+            &Describe {
+                name: "feature".into(),
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let head = description.head.clone().unwrap().sha;
+        let created_at = description.status.info.created_at.clone().unwrap();
+        assert_eq!(
+            format_description(&description),
+            format!(
+                "name:     feature\n\
+                 path:     {}\n\
+                 branch:   feature\n\
+                 head:     {head} Initial commit\n\
+                 upstream: none\n\
+                 status:   dirty\n\
+                 locked:   yes (usb drive)\n\
+                 created:  {created_at} (just now)\n",
+                outcome.path.display()
+            )
+        );
+        let res = describe(
+            &Describe {
+                name: "missing".into(),
+            },
+            &main_wt_path,
+        );
+        assert!(res.unwrap_err().to_string().contains("feature"));
+    }
+
+    #[test]
+    fn describes_current_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let nested = outcome.path.join("src");
+        std::fs::create_dir(&nested).unwrap();
+        for alias in [".", "@"] {
+            let args = Describe { name: alias.into() };
+            let description = describe(&args, &nested).unwrap();
+            assert_eq!(description.status.info.name, "feature");
+            let description = describe(&args, &main_wt_path).unwrap();
+            assert_eq!(description.status.info.name, "trunk");
+        }
+    }
+}
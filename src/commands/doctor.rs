@@ -0,0 +1,178 @@
+use std::path::Path;
+
+use tracing::instrument;
+
+use crate::{
+    git::{
+        check_git, default_branch_name_with_source, get_main_worktree, get_worktrees, project_root,
+    },
+    util::{paint, traceable_path, Stream, CURRENT_STYLE, DIRTY_STYLE},
+};
+
+/// The result of a single diagnostic check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked
+    pub name: &'static str,
+    /// Whether the check passed
+    pub passed: bool,
+    /// What was found
+    pub detail: String,
+    /// How to fix a failed check
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: true,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: false,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Diagnoses common problems with the environment and the project containing `repo_path`
+#[instrument(skip_all, fields(repo_path = traceable_path(repo_path)))]
+pub fn doctor(repo_path: &Path) -> Vec<Check> {
+    let mut checks = vec![
+        match check_git() {
+            Ok((major, minor)) => Check::pass("git", format!("version {major}.{minor}")),
+            Err(err) => Check::fail(
+                "git",
+                err.to_string(),
+                "install a recent version of git and make sure it's on your PATH",
+            ),
+        },
+        default_branch_check(),
+    ];
+    let main_wt = match get_main_worktree(repo_path) {
+        Ok(main_wt) => main_wt,
+        Err(err) => {
+            checks.push(Check::fail(
+                "project",
+                format!("{err:#}"),
+                "run wt from inside a project, or create one with `wt init` or `wt clone`",
+            ));
+            return checks;
+        }
+    };
+    checks.push(match project_root(&main_wt) {
+        Ok(root) => Check::pass("project", format!("root is {}", root.display())),
+        Err(err) => Check::fail(
+            "project",
+            format!("{err:#}"),
+            "run wt from inside a project, or create one with `wt init` or `wt clone`",
+        ),
+    });
+    checks.push(match get_worktrees(&main_wt) {
+        Ok(worktrees) => {
+            let stale = worktrees
+                .iter()
+                .filter(|wt| wt.prunable)
+                .map(|wt| wt.name.as_str())
+                .collect::<Vec<_>>();
+            if stale.is_empty() {
+                Check::pass("worktrees", format!("{} registered", worktrees.len()))
+            } else {
+                Check::fail(
+                    "worktrees",
+                    format!("directories are missing for: {}", stale.join(", ")),
+                    "remove them with `wt rm`, which only forgets a worktree whose directory is gone",
+                )
+            }
+        }
+        Err(err) => Check::fail(
+            "worktrees",
+            format!("{err:#}"),
+            "check that the repository isn't corrupted with `git fsck`",
+        ),
+    });
+    checks
+}
+
+/// Checks which branch `wt init` will create
+fn default_branch_check() -> Check {
+    let name = "default branch";
+    match default_branch_name_with_source(None) {
+        Ok((branch, source)) => Check::pass(
+            name,
+            format!("new projects will use '{branch}', from {source}"),
+        ),
+        Err(err) => Check::fail(
+            name,
+            format!("{err:#}"),
+            "fix your global git config, e.g. ~/.gitconfig, or the branch name in \
+             $WT_DEFAULT_BRANCH or projects.default_branch",
+        ),
+    }
+}
+
+/// Formats the checks as a checklist
+pub fn format_checks(checks: &[Check]) -> String {
+    let mut output = String::new();
+    for check in checks {
+        let mark = if check.passed {
+            paint("[ok]  ", CURRENT_STYLE, Stream::Stdout)
+        } else {
+            paint("[fail]", DIRTY_STYLE, Stream::Stdout)
+        };
+        output.push_str(&format!("{mark} {}: {}\n", check.name, check.detail));
+        if let Some(ref hint) = check.hint {
+            output.push_str(&format!("       hint: {hint}\n"));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::{
+        init::{init, Init},
+        new::{new, New},
+    };
+
+    #[test]
+    fn finds_stale_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert!(doctor(&main_wt_path).iter().all(|c| c.passed));
+        std::fs::remove_dir_all(temp_dir.path().join("test_proj").join("feature")).unwrap();
+        let checks = doctor(&main_wt_path);
+        let worktrees = checks.iter().find(|c| c.name == "worktrees").unwrap();
+        assert!(!worktrees.passed);
+        assert!(worktrees.detail.contains("feature"));
+    }
+
+    #[test]
+    fn reports_missing_project() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let checks = doctor(temp_dir.path());
+        let project = checks.iter().find(|c| c.name == "project").unwrap();
+        assert!(!project.passed);
+    }
+}
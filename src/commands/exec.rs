@@ -0,0 +1,230 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, ExitStatus, Output},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use itertools::Itertools;
+use tracing::{debug, instrument};
+
+use crate::{
+    error::WtError,
+    git::{get_main_worktree, get_worktrees, WorktreeInfo},
+    util::{paint, traceable_path, Stream, EMPHASIS_STYLE},
+    Error,
+};
+
+#[derive(Args, Debug, Clone)]
+pub struct Exec {
+    #[arg(short, long)]
+    #[arg(help = "Keep running the command in the remaining worktrees after a failure")]
+    pub continue_on_error: bool,
+
+    #[arg(short, long, value_name = "N", default_value_t = 1)]
+    #[arg(value_parser = clap::value_parser!(u16).range(1..))]
+    #[arg(help = "The number of worktrees to run the command in concurrently")]
+    pub jobs: u16,
+
+    /// The command to run, where `{name}`, `{branch}`, and `{path}` are replaced with the name,
+    /// branch, and path of each worktree
+    #[arg(value_name = "COMMAND", required = true, trailing_var_arg = true)]
+    pub command: Vec<String>,
+}
+
+/// The result of running the command in a single worktree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecResult {
+    /// The name of the worktree
+    pub name: String,
+    /// The exit status of the command
+    pub status: ExitStatus,
+}
+
+/// Runs a command in every worktree in the project containing `repo_path`
+///
+/// The command's output is printed preceded by a header naming the worktree. When running in
+/// several worktrees concurrently the output of each worktree is buffered and printed all at once
+/// so that the output of different worktrees isn't interleaved.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn exec(args: &Exec, repo_path: &Path) -> Result<Vec<ExecResult>, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let mut worktrees = get_worktrees(&main_wt).context("couldn't get list of worktrees")?;
+    worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+    let results = if args.jobs > 1 {
+        let results = exec_parallel(args, &worktrees)?;
+        print_summary(&results);
+        results
+    } else {
+        exec_serial(args, &worktrees)?
+    };
+    check_results(&results)?;
+    Ok(results)
+}
+
+/// Runs the command in one worktree at a time, streaming its output
+fn exec_serial(args: &Exec, worktrees: &[WorktreeInfo]) -> Result<Vec<ExecResult>, Error> {
+    let mut results = vec![];
+    for wt in worktrees {
+        println!("{}", header(wt));
+        std::io::stdout().flush()?;
+        let status = worktree_command(&args.command, wt)?
+            .status()
+            .with_context(|| format!("couldn't run command in worktree '{}'", wt.name))?;
+        results.push(ExecResult {
+            name: wt.name.clone(),
+            status,
+        });
+        if !status.success() && !args.continue_on_error {
+            bail!("command failed in worktree '{}' ({status})", wt.name);
+        }
+    }
+    Ok(results)
+}
+
+/// Runs the command in several worktrees at once, printing each worktree's output as it finishes
+///
+/// The results are returned in the same order as the worktrees regardless of which order the
+/// commands finished in.
+fn exec_parallel(args: &Exec, worktrees: &[WorktreeInfo]) -> Result<Vec<ExecResult>, Error> {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let results = Mutex::new(Vec::with_capacity(worktrees.len()));
+    let n_threads = (args.jobs as usize).min(worktrees.len());
+    std::thread::scope(|scope| {
+        let handles =
+            (0..n_threads)
+                .map(|_| {
+                    scope.spawn(|| -> Result<(), Error> {
+                        loop {
+                            if failed.load(Ordering::Relaxed) && !args.continue_on_error {
+                                return Ok(());
+                            }
+                            let idx = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(wt) = worktrees.get(idx) else {
+                                return Ok(());
+                            };
+                            let output =
+                                worktree_command(&args.command, wt)?.output().with_context(
+                                    || format!("couldn't run command in worktree '{}'", wt.name),
+                                )?;
+                            print_buffered(wt, &output)?;
+                            if !output.status.success() {
+                                failed.store(true, Ordering::Relaxed);
+                            }
+                            results.lock().expect("results lock poisoned").push((
+                                idx,
+                                ExecResult {
+                                    name: wt.name.clone(),
+                                    status: output.status,
+                                },
+                            ));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("exec thread panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    let mut results = results.into_inner().expect("results lock poisoned");
+    results.sort_by_key(|(idx, _)| *idx);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Returns the header printed before a worktree's output
+fn header(wt: &WorktreeInfo) -> String {
+    paint(
+        format!("==> {} <==", wt.name),
+        EMPHASIS_STYLE,
+        Stream::Stdout,
+    )
+}
+
+/// Prints the captured output of a worktree's command without interleaving it with other output
+fn print_buffered(wt: &WorktreeInfo, output: &Output) -> Result<(), Error> {
+    let mut stdout = std::io::stdout().lock();
+    let mut stderr = std::io::stderr().lock();
+    writeln!(stdout, "{}", header(wt))?;
+    stdout.write_all(&output.stdout)?;
+    stdout.flush()?;
+    stderr.write_all(&output.stderr)?;
+    stderr.flush()?;
+    Ok(())
+}
+
+/// Prints the status of the command in each worktree
+fn print_summary(results: &[ExecResult]) {
+    eprintln!("summary:");
+    for result in results {
+        if result.status.success() {
+            eprintln!("  ok     {}", result.name);
+        } else {
+            eprintln!("  failed {} ({})", result.name, result.status);
+        }
+    }
+}
+
+/// Builds the command to run in the worktree, filling in any placeholders
+fn worktree_command(command: &[String], wt: &WorktreeInfo) -> Result<Command, Error> {
+    let argv = command
+        .iter()
+        .map(|arg| fill_placeholders(arg, wt))
+        .collect::<Vec<_>>();
+    let (program, rest) = argv.split_first().context("no command provided")?;
+    debug!(worktree = wt.name.as_str(), ?argv, "running command");
+    let mut cmd = Command::new(program);
+    cmd.args(rest).current_dir(&wt.path);
+    Ok(cmd)
+}
+
+/// Replaces the `{name}`, `{branch}`, and `{path}` placeholders in the argument
+fn fill_placeholders(arg: &str, wt: &WorktreeInfo) -> String {
+    arg.replace("{name}", &wt.name)
+        .replace("{branch}", wt.branch.as_deref().unwrap_or(""))
+        .replace("{path}", &wt.path.to_string_lossy())
+}
+
+/// Returns an error naming the worktrees the command failed in, if any
+fn check_results(results: &[ExecResult]) -> Result<(), Error> {
+    let failed = results
+        .iter()
+        .filter(|r| !r.status.success())
+        .map(|r| r.name.as_str())
+        .collect::<Vec<_>>();
+    if !failed.is_empty() {
+        bail!("command failed in worktrees: {}", failed.iter().join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn fills_placeholders() {
+        let wt = WorktreeInfo {
+            name: "feature".into(),
+            path: PathBuf::from("/proj/feature"),
+            branch: Some("user/feature".into()),
+            is_main: false,
+            prunable: false,
+            locked: false,
+            lock_reason: None,
+            created_at: None,
+        };
+        assert_eq!(
+            fill_placeholders("{name}:{branch}:{path}", &wt),
+            "feature:user/feature:/proj/feature"
+        );
+    }
+}
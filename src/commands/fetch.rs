@@ -0,0 +1,111 @@
+use std::{fmt::Display, path::Path};
+
+use anyhow::Context;
+use clap::Args;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{fetch_all_and_prune, get_main_worktree, remote_tracking_branches, worktree_or_git_dir},
+    util::traceable_path,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Fetch {}
+
+/// How a remote-tracking branch changed during a fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefChange {
+    /// The remote has a branch that wasn't fetched before
+    New,
+    /// The remote's branch points to a different commit than before
+    Updated,
+    /// The remote's branch is gone, so its remote-tracking branch was pruned
+    Deleted,
+}
+
+impl Display for RefChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RefChange::New => "new",
+            RefChange::Updated => "updated",
+            RefChange::Deleted => "deleted",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Fetches from every remote of the project containing `repo_path` once, returning the
+/// remote-tracking branches that changed sorted by name
+///
+/// Every worktree shares the main worktree's refs, so this brings all of them up to date without
+/// touching any of their branches.
+#[instrument(skip(_args, repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn fetch(_args: &Fetch, repo_path: &Path) -> Result<Vec<(String, RefChange)>, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let path = worktree_or_git_dir(&main_wt)?;
+    let before = remote_tracking_branches(&path)?;
+    fetch_all_and_prune(&path).context("couldn't fetch from remotes")?;
+    let after = remote_tracking_branches(&path)?;
+    let mut changes = after
+        .iter()
+        .filter_map(|(name, sha)| match before.get(name) {
+            None => Some((name.clone(), RefChange::New)),
+            Some(old) if old != sha => Some((name.clone(), RefChange::Updated)),
+            Some(_) => None,
+        })
+        .chain(
+            before
+                .keys()
+                .filter(|name| !after.contains_key(*name))
+                .map(|name| (name.clone(), RefChange::Deleted)),
+        )
+        .collect::<Vec<_>>();
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::clone::{init_via_clone, Clone},
+        git::{create_initial_commit, git_in},
+    };
+
+    #[test]
+    fn reports_changed_remote_branches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let upstream = temp_dir.path().join("upstream");
+        std::fs::create_dir(&upstream).unwrap();
+        gix::init(&upstream).unwrap();
+        create_initial_commit(&upstream).unwrap();
+        git_in(&upstream, &["branch", "old"]);
+        git_in(&upstream, &["branch", "stable"]);
+        let clone_dir = temp_dir.path().join("clones");
+        std::fs::create_dir(&clone_dir).unwrap();
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: upstream.to_string_lossy().to_string(),
+                path: Some(clone_dir),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let default_branch = main_wt_path.file_name().unwrap().to_string_lossy();
+        assert!(fetch(&Fetch {}, &main_wt_path).unwrap().is_empty());
+
+        git_in(&upstream, &["branch", "-D", "old"]);
+        git_in(&upstream, &["branch", "feature"]);
+        git_in(&upstream, &["commit", "--allow-empty", "-m", "New commit"]);
+        assert_eq!(
+            fetch(&Fetch {}, &main_wt_path).unwrap(),
+            vec![
+                ("origin/feature".to_string(), RefChange::New),
+                (format!("origin/{default_branch}"), RefChange::Updated),
+                ("origin/old".to_string(), RefChange::Deleted),
+            ]
+        );
+    }
+}
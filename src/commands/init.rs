@@ -1,42 +1,374 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Context;
+use anyhow::{anyhow, bail, Context};
 use clap::Args;
+use itertools::Itertools;
 use tracing::{debug, instrument};
 
 use crate::{
-    git::{create_initial_commit, global_default_branch_name},
+    config::{default_layout, default_projects_dir, Layout},
+    error::WtError,
+    git::{
+        add_paths, check_identity_configured, check_signing_configured, create_initial_commit_bare,
+        create_initial_commit_with, default_branch_name, new_worktree, set_project_layout,
+        set_unborn_branch, signs_commits, validate_branch_name, Identity, InitialCommitOptions,
+    },
+    util::{dir_is_occupied, traceable_path},
     Error,
 };
 
-#[derive(Args, Debug, Clone)]
+#[derive(Args, Debug, Clone, Default)]
 pub struct Init {
     /// The name of the project and the parent directory of the worktrees
     #[arg(value_name = "PROJECT_NAME")]
     pub name: String,
 
     #[arg(short, long, value_name = "PATH")]
-    #[arg(help = "The path under which to create the project in")]
+    #[arg(
+        help = "The path under which to create the project [default: WT_PROJECTS_DIR, projects.dir, or the current directory]"
+    )]
     pub path: Option<PathBuf>,
+
+    #[arg(short, long, value_name = "NAME")]
+    #[arg(help = "The name of the initial branch [default: init.defaultBranch]")]
+    pub branch: Option<String>,
+
+    #[arg(short, long)]
+    #[arg(help = "Create the project even if the project directory already exists")]
+    pub force: bool,
+
+    #[arg(long)]
+    #[arg(help = "Keep a bare repository at the root of the project")]
+    pub bare: bool,
+
+    #[arg(long, value_enum, value_name = "LAYOUT")]
+    #[arg(help = "Where to put the project's worktrees [default: projects.layout, or flat]")]
+    pub layout: Option<Layout>,
+
+    #[arg(short, long)]
+    #[arg(help = "Sign the initial commit [default: commit.gpgSign]")]
+    pub sign: bool,
+
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "bare")]
+    #[arg(
+        help = "Add a .gitignore to the initial commit from a built-in template (rust, node, python) or a file"
+    )]
+    pub gitignore: Option<String>,
+
+    #[arg(long, value_name = "'NAME <EMAIL>'")]
+    #[arg(help = "Make the initial commit as this identity [default: user.name and user.email]")]
+    pub author: Option<Identity>,
 }
 
 /// Creates a new worktree project
 #[instrument]
-pub fn init(args: &Init) -> Result<PathBuf, Error> {
-    let branch_name = global_default_branch_name()?;
-    let parent_path = if let Some(p) = &args.path {
-        p.clone()
-    } else {
-        std::env::current_dir().context("couldn't get current directory")?
+pub fn init(args: &Init) -> Result<PathBuf, WtError> {
+    let current_dir = std::env::current_dir().context("couldn't get current directory")?;
+    let parent_path = resolve_parent_path(args.path.as_deref(), &current_dir)?;
+    let branch_name = match &args.branch {
+        Some(branch) => {
+            validate_branch_name(branch)?;
+            branch.clone()
+        }
+        // Creating the project inside another repository picks up that repository's settings,
+        // the same as `git init` would
+        None => default_branch_name(gix::discover(&parent_path).ok().as_ref())?,
+    };
+    let gitignore = args
+        .gitignore
+        .as_deref()
+        .map(|template| gitignore_contents(template, &current_dir))
+        .transpose()?;
+    let project_path = parent_path.join(&args.name);
+    if !args.force && dir_is_occupied(&project_path)? {
+        return Err(anyhow!(
+            "project directory already exists and isn't empty: {} (use --force to initialize anyway)",
+            project_path.display()
+        ).into());
+    }
+    // Nothing has been created yet, so failing here leaves nothing behind to get in the way of
+    // trying again
+    let commit_options = initial_commit_options(args, None)?;
+    let layout = match args.layout {
+        Some(layout) => layout,
+        None => default_layout()?,
     };
-    let path = parent_path.join(&args.name).join(branch_name);
-    std::fs::create_dir_all(&path)?;
+    let path = layout.worktrees_dir(&project_path).join(&branch_name);
+    if args.bare {
+        return Ok(init_bare(
+            &project_path,
+            &path,
+            &branch_name,
+            layout,
+            &commit_options,
+        )?);
+    }
+    std::fs::create_dir_all(&path).context("failed to create project directory")?;
     debug!(
         path = path.to_string_lossy().as_ref(),
         "initializing new repository"
     );
-    let _repo = gix::init(&path).context("failed to init git repository")?;
+    gix::init(&path).context("failed to init git repository")?;
+    set_project_layout(&path, layout)?;
+    // The branch name may differ from what `init.defaultBranch` created HEAD with
+    set_unborn_branch(&path, &branch_name)?;
+    if let Some(contents) = gitignore {
+        std::fs::write(path.join(".gitignore"), contents).context("couldn't write .gitignore")?;
+        add_paths(&path, &[".gitignore"])?;
+    }
     // TODO: use gix for this
-    create_initial_commit(&path)?;
+    create_initial_commit_with(&path, &commit_options)?;
+    Ok(path)
+}
+
+/// Creates a project whose main repository is a bare `.git` directory at the project root
+///
+/// The worktree for the initial branch is created alongside the bare repository just like any
+/// other worktree would be.
+fn init_bare(
+    project_path: &Path,
+    wt_path: &Path,
+    branch_name: &str,
+    layout: Layout,
+    commit_options: &InitialCommitOptions,
+) -> Result<PathBuf, Error> {
+    let git_dir = project_path.join(".git");
+    std::fs::create_dir_all(&git_dir)?;
+    debug!(
+        path = traceable_path(&git_dir),
+        "initializing new bare repository"
+    );
+    gix::init_bare(&git_dir).context("failed to init bare git repository")?;
+    set_project_layout(&git_dir, layout)?;
+    set_unborn_branch(&git_dir, branch_name)?;
+    create_initial_commit_bare(&git_dir, branch_name, commit_options)?;
+    new_worktree(&git_dir, wt_path, branch_name)
+        .context("couldn't create worktree for initial branch")?;
+    Ok(wt_path.to_path_buf())
+}
+
+/// The `.gitignore` templates that `--gitignore` accepts by name
+const GITIGNORE_TEMPLATES: &[(&str, &str)] = &[
+    ("node", include_str!("../gitignore/node.gitignore")),
+    ("python", include_str!("../gitignore/python.gitignore")),
+    ("rust", include_str!("../gitignore/rust.gitignore")),
+];
+
+/// Returns the contents of the `.gitignore` for a built-in template or a file, which is relative
+/// to `current_dir`
+fn gitignore_contents(template: &str, current_dir: &Path) -> Result<String, Error> {
+    if let Some((_, contents)) = GITIGNORE_TEMPLATES
+        .iter()
+        .find(|(name, _)| *name == template)
+    {
+        return Ok(contents.to_string());
+    }
+    let path = current_dir.join(template);
+    if !path.is_file() {
+        bail!(
+            "'{template}' isn't a built-in .gitignore template ({}) or a file",
+            GITIGNORE_TEMPLATES.iter().map(|(name, _)| *name).join(", ")
+        );
+    }
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("couldn't read .gitignore template: {}", path.display()))
+}
+
+/// Decides how to create the initial commit, checking up front that git knows who to make it as
+/// and that it can be signed if it has to be
+///
+/// Without a repository the global config is checked, which is all that a new repository has.
+fn initial_commit_options(
+    args: &Init,
+    repo: Option<&gix::Repository>,
+) -> Result<InitialCommitOptions, Error> {
+    if args.author.is_none() {
+        check_identity_configured(repo)?;
+    }
+    let sign = args.sign || signs_commits(repo)?;
+    if sign {
+        check_signing_configured(repo)?;
+    }
+    Ok(InitialCommitOptions {
+        sign,
+        author: args.author.clone(),
+    })
+}
+
+/// Returns the directory to create the project under, which must already exist
+///
+/// Without a path this is the configured projects directory, falling back to `current_dir`.
+/// Relative paths are relative to `current_dir`.
+pub(crate) fn resolve_parent_path(
+    path: Option<&Path>,
+    current_dir: &Path,
+) -> Result<PathBuf, Error> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match default_projects_dir()? {
+            Some(dir) => dir,
+            None => return Ok(current_dir.to_path_buf()),
+        },
+    };
+    let path = if path.is_relative() {
+        current_dir.join(path)
+    } else {
+        path.to_path_buf()
+    };
+    if !path.exists() {
+        bail!("path does not exist: {}", path.display());
+    }
     Ok(path)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::git::{current_branch_name, git_in};
+
+    #[test]
+    fn signs_when_git_is_configured_to() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        git_in(temp_dir.path(), &["init", "-q"]);
+        git_in(temp_dir.path(), &["config", "commit.gpgSign", "true"]);
+        let repo = gix::open(temp_dir.path()).unwrap();
+        let args = Init {
+            author: Some("Jane Doe <jane@example.com>".parse().unwrap()),
+            ..Default::default()
+        };
+        // Failures should be explained as signing problems even without --sign
+        assert!(initial_commit_options(&args, Some(&repo)).unwrap().sign);
+    }
+
+    #[test]
+    fn refuses_occupied_project_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project_dir = temp_dir.path().join("test_proj");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("file.txt"), "contents").unwrap();
+        let res = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        });
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn allows_empty_project_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("test_proj")).unwrap();
+        let res = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        });
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn resolves_relative_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        assert_eq!(
+            resolve_parent_path(Some(Path::new("sub")), temp_dir.path()).unwrap(),
+            temp_dir.path().join("sub")
+        );
+        assert_eq!(
+            resolve_parent_path(None, temp_dir.path()).unwrap(),
+            temp_dir.path()
+        );
+        assert!(resolve_parent_path(Some(Path::new("missing")), temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn uses_provided_branch_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            main_wt_path,
+            temp_dir.path().join("test_proj").join("trunk")
+        );
+        let repo = gix::open(&main_wt_path).unwrap();
+        assert_eq!(current_branch_name(&repo).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn rejects_invalid_branch_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let res = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("not a branch".into()),
+            ..Default::default()
+        });
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn creates_bare_layout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            bare: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let project_dir = temp_dir.path().join("test_proj");
+        assert_eq!(main_wt_path, project_dir.join("trunk"));
+        let wt = gix::open(&main_wt_path).unwrap();
+        assert!(wt.main_repo().unwrap().is_bare());
+        assert_eq!(current_branch_name(&wt).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn commits_gitignore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            gitignore: Some("rust".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let contents = std::fs::read_to_string(main_wt_path.join(".gitignore")).unwrap();
+        assert!(contents.contains("/target/"));
+        let repo = gix::open(&main_wt_path).unwrap();
+        let tree = repo.head_commit().unwrap().tree().unwrap();
+        assert!(tree.find_entry(".gitignore").is_some());
+
+        let custom = temp_dir.path().join("custom.gitignore");
+        std::fs::write(&custom, "*.log\n").unwrap();
+        assert_eq!(
+            gitignore_contents("custom.gitignore", temp_dir.path()).unwrap(),
+            "*.log\n"
+        );
+        let err = gitignore_contents("cobol", temp_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("node, python, rust"));
+    }
+
+    #[test]
+    fn creates_grouped_bare_layout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            bare: true,
+            layout: Some(Layout::Grouped),
+            ..Default::default()
+        })
+        .unwrap();
+        let project_dir = temp_dir.path().join("test_proj");
+        assert_eq!(main_wt_path, project_dir.join("worktrees").join("trunk"));
+        assert!(project_dir.join(".git").is_dir());
+    }
+}
@@ -1,26 +1,753 @@
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
 use anyhow::Context;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
 use tracing::instrument;
 
 use crate::{
-    git::{get_main_worktree, get_worktrees, global_default_branch_name},
+    error::WtError,
+    git::{
+        get_main_worktree, get_worktrees, head_commit, main_worktree_info, worktree_list_porcelain,
+        worktree_or_git_dir, HeadCommit, ProjectRepos, WorktreeInfo,
+    },
+    util::{
+        paint, timestamp_seconds, traceable_path, Stream, CURRENT_STYLE, EMPHASIS_STYLE,
+        LOCKED_STYLE,
+    },
     Error,
 };
 
-/// Remove one or more worktrees
-#[instrument]
-pub fn list() -> Result<(), Error> {
-    let main_wt =
-        get_main_worktree(std::env::current_dir().context("couldn't get current directory")?)
-            .context("couldn't get main worktree")?;
-    let default_branch = global_default_branch_name().context("couldn't get default branch")?;
-    let mut worktrees = get_worktrees(&main_wt)
+#[derive(Args, Debug, Clone, Default)]
+pub struct List {
+    #[arg(long, conflicts_with = "porcelain")]
+    #[arg(help = "Print the worktrees as JSON")]
+    pub json: bool,
+
+    #[arg(long)]
+    #[arg(
+        help = "Print every worktree, including the main one, like `git worktree list --porcelain`"
+    )]
+    pub porcelain: bool,
+
+    #[arg(long, conflicts_with_all = ["json", "porcelain"])]
+    #[arg(help = "Print the absolute path of each worktree instead of its name")]
+    pub absolute: bool,
+
+    #[arg(short, long, conflicts_with_all = ["porcelain", "absolute"])]
+    #[arg(help = "Show the SHA and subject of the commit checked out in each worktree")]
+    pub commits: bool,
+
+    #[arg(short, long, conflicts_with_all = ["json", "porcelain", "absolute", "commits"])]
+    #[arg(
+        help = "Print every worktree, including the main one, with its path, commit, and branch like `git worktree list`"
+    )]
+    pub long: bool,
+
+    #[arg(long)]
+    #[arg(help = "Only show worktrees whose directories are missing, which git would prune")]
+    pub stale: bool,
+
+    #[arg(short, long, visible_alias = "include-main", conflicts_with = "stale")]
+    #[arg(help = "Include the main worktree, listed first and marked with (main)")]
+    pub all: bool,
+
+    #[arg(short, long, value_enum, default_value_t = SortBy::Name)]
+    #[arg(help = "How to sort the worktrees")]
+    pub sort: SortBy,
+
+    #[arg(short, long)]
+    #[arg(help = "Reverse the sort order")]
+    pub reverse: bool,
+
+    #[arg(short, long, conflicts_with_all = ["json", "porcelain", "absolute", "long"])]
+    #[arg(
+        help = "Group the worktrees under headers by the prefix of their branch, e.g. `feature/`"
+    )]
+    pub group: bool,
+}
+
+/// The orders that worktrees can be listed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortBy {
+    /// Sort by worktree name
+    #[default]
+    Name,
+    /// Sort by when the worktree directory was created, oldest first
+    Created,
+    /// Sort by the name of the checked out branch, with detached worktrees last
+    Branch,
+}
+
+/// A worktree as it appears in the listing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListEntry {
+    #[serde(flatten)]
+    pub info: WorktreeInfo,
+    /// Whether this is the worktree containing the path `wt` was called from
+    pub is_current: bool,
+    /// The commit checked out in the worktree, only looked up with `--commits` or `--long`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<HeadCommit>,
+}
+
+/// Returns the worktrees in the project containing `repo_path`, in the order requested by `args`
+///
+/// The main worktree is only included with `--long` or `--all`, where it always comes first.
+/// Worktrees that sort equally are ordered by name.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn list(args: &List, repo_path: &Path) -> Result<Vec<ListEntry>, WtError> {
+    let repos = ProjectRepos::discover(repo_path).context("couldn't get main worktree")?;
+    let main_wt = &repos.main;
+    let current_wt_path = repos
+        .current_worktree_path()
+        .and_then(|p| p.canonicalize().ok());
+    let to_entry = |info: WorktreeInfo| {
+        let is_current =
+            current_wt_path.is_some() && info.path.canonicalize().ok() == current_wt_path;
+        let head = if (args.commits || args.long) && !info.prunable {
+            head_commit(&info.path)
+                .with_context(|| format!("couldn't read HEAD of worktree '{}'", info.name))?
+        } else {
+            None
+        };
+        Ok(ListEntry {
+            info,
+            is_current,
+            head,
+        })
+    };
+    let mut worktrees = get_worktrees(main_wt)
         .context("couldn't get list of worktrees")?
         .into_iter()
-        .filter(|name| name != &default_branch)
+        .filter(|info| !args.stale || info.prunable)
+        .map(to_entry)
+        .collect::<Result<Vec<_>, Error>>()?;
+    worktrees.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+    match args.sort {
+        SortBy::Name => {}
+        SortBy::Created => {
+            worktrees.sort_by_cached_key(|entry| created_at(&entry.info));
+        }
+        SortBy::Branch => {
+            worktrees.sort_by(|a, b| {
+                (a.info.branch.is_none(), &a.info.branch)
+                    .cmp(&(b.info.branch.is_none(), &b.info.branch))
+            });
+        }
+    }
+    if args.reverse {
+        worktrees.reverse();
+    }
+    if (args.long || args.all) && !args.stale {
+        if let Some(info) = main_worktree_info(main_wt)? {
+            worktrees.insert(0, to_entry(info)?);
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Returns when the worktree was created
+///
+/// This is the time `wt` recorded when it created the worktree, falling back to when its directory
+/// was created, or last modified on filesystems that don't record creation times.
+fn created_at(info: &WorktreeInfo) -> Option<SystemTime> {
+    if let Some(seconds) = info.created_at.as_deref().and_then(timestamp_seconds) {
+        return Some(UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64));
+    }
+    let metadata = std::fs::metadata(&info.path).ok()?;
+    metadata.created().or_else(|_| metadata.modified()).ok()
+}
+
+/// Returns every worktree in the project containing `repo_path`, including the main worktree, in
+/// the format of `git worktree list --porcelain`
+///
+/// With `--stale` only the worktrees that git reports as prunable are included.
+#[instrument(skip_all, fields(repo_path = traceable_path(repo_path)))]
+pub fn porcelain_listing(args: &List, repo_path: &Path) -> Result<String, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let listing = worktree_list_porcelain(worktree_or_git_dir(&main_wt)?)?;
+    if !args.stale {
+        return Ok(listing);
+    }
+    Ok(listing
+        .split_inclusive("\n\n")
+        .filter(|stanza| stanza.lines().any(|line| line.starts_with("prunable")))
+        .collect())
+}
+
+/// Formats the listing for display
+pub fn format_listing(entries: &[ListEntry], args: &List) -> Result<String, WtError> {
+    if args.json {
+        return Ok(serde_json::to_string_pretty(entries).context("couldn't serialize worktrees")?);
+    }
+    if args.long {
+        return Ok(long_listing(entries));
+    }
+    let name_width = entries.iter().map(|e| e.info.name.len()).max().unwrap_or(0);
+    let mut output = String::new();
+    if args.absolute {
+        // Meant for piping into other tools, so there are no markers to strip off
+        for entry in entries {
+            output.push_str(&format!("{}\n", entry.info.path.display()));
+        }
+        return Ok(output);
+    }
+    if args.group {
+        let mut groups: BTreeMap<_, Vec<&ListEntry>> = BTreeMap::new();
+        for entry in entries {
+            let prefix = group_prefix(entry);
+            groups
+                .entry((prefix.is_none(), prefix))
+                .or_default()
+                .push(entry);
+        }
+        for (i, ((_, prefix), group)) in groups.into_iter().enumerate() {
+            if i > 0 {
+                output.push('\n');
+            }
+            let header = match prefix {
+                Some(prefix) => format!("{prefix}/"),
+                None => "(no prefix)".to_string(),
+            };
+            output.push_str(&paint(header, EMPHASIS_STYLE, Stream::Stdout));
+            output.push('\n');
+            for entry in group {
+                push_entry(&mut output, entry, args, name_width);
+            }
+        }
+        return Ok(output);
+    }
+    for entry in entries {
+        push_entry(&mut output, entry, args, name_width);
+    }
+    Ok(output)
+}
+
+/// Returns the group a worktree is listed under with `--group`
+///
+/// This is the leading path component of its branch, or of its name when it's detached, if there is
+/// more than one component.
+fn group_prefix(entry: &ListEntry) -> Option<&str> {
+    let label = entry.info.branch.as_deref().unwrap_or(&entry.info.name);
+    label
+        .split_once('/')
+        .map(|(prefix, _)| prefix)
+        .filter(|prefix| !prefix.is_empty())
+}
+
+/// Appends the line for a single worktree to the short listing
+fn push_entry(output: &mut String, entry: &ListEntry, args: &List, name_width: usize) {
+    let name = if args.commits {
+        format!("{:name_width$}", entry.info.name)
+    } else {
+        entry.info.name.clone()
+    };
+    if entry.is_current {
+        let line = format!("* {name}");
+        output.push_str(&paint(line, CURRENT_STYLE, Stream::Stdout));
+    } else {
+        output.push_str(&format!("  {name}"));
+    }
+    if args.commits {
+        match entry.head {
+            Some(ref head) => output.push_str(&format!(
+                "  {}  {}",
+                head.sha,
+                truncate_subject(&head.subject)
+            )),
+            None => output.push_str("  (no commit)"),
+        }
+    }
+    if entry.info.is_main {
+        output.push_str(" (main)");
+    }
+    if entry.info.prunable {
+        output.push_str(" (prunable)");
+    }
+    if entry.info.locked {
+        let lock = match entry.info.lock_reason {
+            Some(ref reason) => format!(" (locked: {reason})"),
+            None => " (locked)".to_string(),
+        };
+        output.push_str(&paint(lock, LOCKED_STYLE, Stream::Stdout));
+    }
+    output.push('\n');
+}
+
+/// Formats the listing like `git worktree list`, with aligned path, short SHA, and branch columns
+fn long_listing(entries: &[ListEntry]) -> String {
+    let paths = entries
+        .iter()
+        .map(|e| e.info.path.display().to_string())
         .collect::<Vec<_>>();
-    worktrees.sort();
-    for name in worktrees {
-        println!("{}", name);
+    let path_width = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+    let mut output = String::new();
+    for (entry, path) in entries.iter().zip(paths) {
+        let sha = match entry.head {
+            Some(ref head) => head.sha.as_str(),
+            None => "0000000",
+        };
+        let branch = match entry.info.branch {
+            Some(ref branch) => format!("[{branch}]"),
+            None => "(detached HEAD)".to_string(),
+        };
+        let line = format!("{path:path_width$} {sha} {branch}");
+        if entry.is_current {
+            output.push_str(&paint(line, CURRENT_STYLE, Stream::Stdout));
+        } else {
+            output.push_str(&line);
+        }
+        if entry.info.locked {
+            output.push_str(&paint(" locked", LOCKED_STYLE, Stream::Stdout));
+        }
+        if entry.info.prunable {
+            output.push_str(" prunable");
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Longer commit subjects are truncated so the columns of the listing stay tidy
+const MAX_SUBJECT_LEN: usize = 50;
+
+/// Truncates a commit subject to [MAX_SUBJECT_LEN] characters, marking where it was cut off
+fn truncate_subject(subject: &str) -> String {
+    if subject.chars().count() <= MAX_SUBJECT_LEN {
+        return subject.to_string();
+    }
+    let truncated = subject
+        .chars()
+        .take(MAX_SUBJECT_LEN - 1)
+        .collect::<String>();
+    format!("{}…", truncated.trim_end())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{
+            init::{init, Init},
+            new::{new, New},
+        },
+        git::{default_branch_name, lock_worktree},
+    };
+
+    fn names(entries: &[ListEntry]) -> Vec<String> {
+        entries.iter().map(|e| e.info.name.clone()).collect()
+    }
+
+    #[test]
+    fn lists_worktree_named_like_default_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        // The old filtering hid any worktree whose name matched the default branch
+        let default_branch = default_branch_name(None).unwrap();
+        let wt_name = if default_branch == "trunk" {
+            "main".to_string()
+        } else {
+            default_branch
+        };
+        new(
+            &New {
+                name: Some(wt_name.clone()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            names(&list(&List::default(), &main_wt_path).unwrap()),
+            vec![wt_name]
+        );
+    }
+
+    #[test]
+    fn excludes_main_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(list(&List::default(), &main_wt_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn includes_main_worktree_with_all() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let args = List {
+            all: true,
+            reverse: true,
+            ..Default::default()
+        };
+        let feature_path = temp_dir.path().join("test_proj").join("feature");
+        let entries = list(&args, &feature_path).unwrap();
+        assert_eq!(names(&entries), vec!["trunk", "feature"]);
+        assert_eq!(
+            format_listing(&entries, &args).unwrap(),
+            "  trunk (main)\n* feature\n"
+        );
+    }
+
+    #[test]
+    fn marks_prunable_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["gone", "here"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        std::fs::remove_dir_all(temp_dir.path().join("test_proj").join("gone")).unwrap();
+        let args = List {
+            commits: true,
+            ..Default::default()
+        };
+        let entries = list(&args, &main_wt_path).unwrap();
+        let prunable = entries.iter().map(|e| e.info.prunable).collect::<Vec<_>>();
+        assert_eq!(prunable, vec![true, false]);
+        let output = format_listing(&entries, &List::default()).unwrap();
+        assert_eq!(output, "  gone (prunable)\n  here\n");
+        let stale = List {
+            stale: true,
+            ..Default::default()
+        };
+        assert_eq!(names(&list(&stale, &main_wt_path).unwrap()), vec!["gone"]);
+    }
+
+    #[test]
+    fn marks_current_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["bar", "foo"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let nested = temp_dir.path().join("test_proj").join("foo").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        let entries = list(&List::default(), &nested).unwrap();
+        let current = entries
+            .iter()
+            .filter(|e| e.is_current)
+            .map(|e| e.info.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(current, vec!["foo"]);
+        let output = format_listing(&entries, &List::default()).unwrap();
+        assert_eq!(output, "  bar\n* foo\n");
+    }
+
+    #[test]
+    fn shows_locked_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["bar", "baz", "foo"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let proj = temp_dir.path().join("test_proj");
+        lock_worktree(&main_wt_path, proj.join("bar"), None).unwrap();
+        lock_worktree(&main_wt_path, proj.join("foo"), Some("usb drive")).unwrap();
+        let entries = list(&List::default(), &main_wt_path).unwrap();
+        let output = format_listing(&entries, &List::default()).unwrap();
+        assert_eq!(output, "  bar (locked)\n  baz\n  foo (locked: usb drive)\n");
+        let paths = format_listing(
+            &entries,
+            &List {
+                absolute: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let expected = ["bar", "baz", "foo"]
+            .map(|name| format!("{}\n", proj.join(name).display()))
+            .concat();
+        assert_eq!(paths, expected);
+        let json = format_listing(
+            &entries,
+            &List {
+                json: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(json.contains("\"lock_reason\": \"usb drive\""));
+    }
+
+    #[test]
+    fn sorts_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for (day, (name, branch)) in [("b", "3"), ("c", "1"), ("a", "2")].iter().enumerate() {
+            let outcome = new(
+                &New {
+                    name: Some(name.to_string()),
+                    new_branch: Some(branch.to_string()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+            // Recorded creation times only have a resolution of one second, so spread them out
+            let git_dir = gix::open(&outcome.path).unwrap().git_dir().to_path_buf();
+            let created_at = format!("2024-05-0{}T12:00:00+00:00", day + 1);
+            std::fs::write(git_dir.join("wt-created-at"), created_at).unwrap();
+        }
+        let sorted = |sort, reverse| {
+            names(
+                &list(
+                    &List {
+                        sort,
+                        reverse,
+                        ..Default::default()
+                    },
+                    &main_wt_path,
+                )
+                .unwrap(),
+            )
+        };
+        assert_eq!(sorted(SortBy::Name, false), vec!["a", "b", "c"]);
+        assert_eq!(sorted(SortBy::Name, true), vec!["c", "b", "a"]);
+        assert_eq!(sorted(SortBy::Branch, false), vec!["c", "a", "b"]);
+        assert_eq!(sorted(SortBy::Created, false), vec!["b", "c", "a"]);
+        assert_eq!(sorted(SortBy::Created, true), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn shows_head_commits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["a", "longer"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let args = List {
+            commits: true,
+            ..Default::default()
+        };
+        let entries = list(&args, &main_wt_path).unwrap();
+        let sha = entries[0].head.clone().unwrap().sha;
+        assert_eq!(
+            format_listing(&entries, &args).unwrap(),
+            format!("  a       {sha}  Initial commit\n  longer  {sha}  Initial commit\n")
+        );
+    }
+
+    #[test]
+    fn groups_by_branch_prefix() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for (name, branch) in [
+            ("login", "fix/login"),
+            ("search", "feature/search"),
+            ("api", "feature/api"),
+            ("scratch", "scratch"),
+        ] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    new_branch: Some(branch.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let args = List {
+            group: true,
+            ..Default::default()
+        };
+        let entries = list(&args, &main_wt_path).unwrap();
+        assert_eq!(
+            format_listing(&entries, &args).unwrap(),
+            "feature/\n  api\n  search\n\nfix/\n  login\n\n(no prefix)\n  scratch\n"
+        );
+    }
+
+    #[test]
+    fn lists_like_git_worktree_list() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        new(
+            &New {
+                name: Some("feature".into()),
+                new_branch: Some("alice/feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let args = List {
+            long: true,
+            ..Default::default()
+        };
+        let entries = list(&args, &main_wt_path).unwrap();
+        assert_eq!(names(&entries), vec!["trunk", "feature"]);
+        let sha = entries[0].head.clone().unwrap().sha;
+        let feature_path = temp_dir.path().join("test_proj").join("feature");
+        let main_path = format!(
+            "{:width$}",
+            main_wt_path.display(),
+            width = feature_path.display().to_string().len()
+        );
+        assert_eq!(
+            format_listing(&entries, &args).unwrap(),
+            format!(
+                "{main_path} {sha} [trunk]\n{} {sha} [alice/feature]\n",
+                feature_path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn truncates_long_subjects() {
+        assert_eq!(truncate_subject("Fix login redirect"), "Fix login redirect");
+        let long = "a".repeat(60);
+        let truncated = truncate_subject(&long);
+        assert_eq!(truncated.chars().count(), MAX_SUBJECT_LEN);
+        assert!(truncated.ends_with('…'));
+    }
+
+    #[test]
+    fn lists_stale_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["gone", "present"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        std::fs::remove_dir_all(temp_dir.path().join("test_proj").join("gone")).unwrap();
+        let args = List {
+            stale: true,
+            ..Default::default()
+        };
+        assert_eq!(names(&list(&args, &main_wt_path).unwrap()), vec!["gone"]);
+        let porcelain = porcelain_listing(&args, &main_wt_path).unwrap();
+        assert_eq!(porcelain.trim().split("\n\n").count(), 1);
+        assert!(porcelain.contains("branch refs/heads/gone\nprunable"));
+    }
+
+    #[test]
+    fn porcelain_includes_main_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let output = porcelain_listing(&List::default(), &main_wt_path).unwrap();
+        let stanzas = output.trim().split("\n\n").collect::<Vec<_>>();
+        assert_eq!(stanzas.len(), 2);
+        assert!(stanzas[0].starts_with(&format!("worktree {}\n", main_wt_path.display())));
+        assert!(stanzas[0].ends_with("branch refs/heads/trunk"));
+        assert!(stanzas[1].ends_with("branch refs/heads/feature"));
     }
-    Ok(())
 }
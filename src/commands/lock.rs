@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use clap::Args;
+use itertools::Itertools;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{
+        get_main_worktree, get_worktrees, lock_worktree, unlock_worktree, worktree_or_git_dir,
+        WorktreeInfo,
+    },
+    util::traceable_path,
+    Error,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Lock {
+    #[arg(value_name = "WT_NAME")]
+    pub name: String,
+
+    #[arg(short, long)]
+    #[arg(help = "Why the worktree is being locked")]
+    pub reason: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Unlock {
+    #[arg(value_name = "WT_NAME")]
+    pub name: String,
+}
+
+/// Locks a worktree in the project containing `repo_path` so it can't be removed
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn lock(args: &Lock, repo_path: &Path) -> Result<(), WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let (info, path) = find_worktree(&main_wt, &args.name)?;
+    if info.locked {
+        return Err(anyhow!(
+            "worktree '{}' is already locked{}",
+            args.name,
+            describe_reason(&info.lock_reason)
+        )
+        .into());
+    }
+    lock_worktree(worktree_or_git_dir(&main_wt)?, path, args.reason.as_deref())
+        .with_context(|| format!("couldn't lock worktree '{}'", args.name))?;
+    Ok(())
+}
+
+/// Unlocks a worktree in the project containing `repo_path`, returning why it was locked
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn unlock(args: &Unlock, repo_path: &Path) -> Result<Option<String>, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let (info, path) = find_worktree(&main_wt, &args.name)?;
+    if !info.locked {
+        return Err(anyhow!("worktree '{}' isn't locked", args.name).into());
+    }
+    unlock_worktree(worktree_or_git_dir(&main_wt)?, path)
+        .with_context(|| format!("couldn't unlock worktree '{}'", args.name))?;
+    Ok(info.lock_reason)
+}
+
+/// Formats a lock reason for appending to a message about a locked worktree
+pub fn describe_reason(reason: &Option<String>) -> String {
+    match reason {
+        Some(reason) => format!(" ({reason})"),
+        None => String::new(),
+    }
+}
+
+/// Looks up a registered worktree by name, returning its info and the path to operate on
+fn find_worktree(main_wt: &gix::Repository, name: &str) -> Result<(WorktreeInfo, PathBuf), Error> {
+    let worktrees = get_worktrees(main_wt).context("couldn't get list of worktrees")?;
+    let Some(info) = worktrees.iter().find(|wt| wt.name == name) else {
+        bail!(
+            "no worktree named '{name}', known worktrees:\n{}",
+            worktrees.iter().map(|wt| &wt.name).sorted().join("\n")
+        );
+    };
+    Ok((info.clone(), info.path.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::{
+        init::{init, Init},
+        new::{new, New},
+    };
+
+    #[test]
+    fn locks_and_unlocks_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        lock(
+            &Lock {
+                name: "feature".into(),
+                reason: Some("on a usb drive".into()),
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let main_wt = gix::open(&main_wt_path).unwrap();
+        let info = &get_worktrees(&main_wt).unwrap()[0];
+        assert!(info.locked);
+        assert_eq!(info.lock_reason.as_deref(), Some("on a usb drive"));
+
+        let unlock_args = Unlock {
+            name: "feature".into(),
+        };
+        let reason = unlock(&unlock_args, &main_wt_path).unwrap();
+        assert_eq!(reason.as_deref(), Some("on a usb drive"));
+        assert!(!get_worktrees(&main_wt).unwrap()[0].locked);
+        assert!(unlock(&unlock_args, &main_wt_path).is_err());
+    }
+}
@@ -1,20 +1,36 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use clap::Args;
 use gix::Repository;
+use itertools::Itertools;
 use tracing::{debug, instrument};
 
 use crate::{
-    git::{create_branch, get_main_worktree, new_worktree, worktree_path},
+    config::{load_config, DirNames},
+    error::WtError,
+    git::{
+        enable_worktree_config, fetch_refspec, new_worktree, new_worktree_with_branch,
+        project_root, project_worktree_path, record_creation_time, remote_url, set_worktree_config,
+        validate_branch_name, worktree_or_git_dir, worktree_path, worktree_with_branch,
+        ProjectRepos,
+    },
+    project_lock::ProjectLock,
+    prompt::{can_confirm, confirm},
     util::traceable_path,
     Error,
 };
 
-#[derive(Args, Debug, Clone)]
+#[derive(Args, Debug, Clone, Default)]
 pub struct New {
     #[arg(value_name = "DIR_NAME")]
-    pub name: String,
+    #[arg(required_unless_present_any = ["branch_name", "new_branch"])]
+    #[arg(help = "Name of the worktree [default: the name of the --branch-name or --new-branch]")]
+    pub name: Option<String>,
 
     #[arg(short, long, group = "branch", value_name = "EXISTING_BRANCH")]
     #[arg(help = "Check out an existing branch (can't be checked out anywhere else)")]
@@ -24,62 +40,657 @@ pub struct New {
     #[arg(help = "Create a new branch with a name different from the directory name")]
     pub new_branch: Option<String>,
 
+    #[arg(long, value_name = "NUMBER", conflicts_with = "branch_name")]
+    #[arg(help = "Check out a GitHub pull request from the origin remote")]
+    pub pr: Option<u32>,
+
+    #[arg(long, value_name = "START_POINT", conflicts_with_all = ["branch_name", "pr"])]
+    #[arg(
+        help = "Start the new branch at this commit or branch, e.g. origin/main [default: HEAD]"
+    )]
+    pub from: Option<String>,
+
+    #[arg(long, visible_alias = "track", conflicts_with_all = ["branch_name", "pr"])]
+    #[arg(
+        help = "Set the new branch's upstream to the start point [default: branch.autoSetupMerge]"
+    )]
+    pub set_upstream: bool,
+
+    #[arg(long, conflicts_with_all = ["set_upstream", "branch_name", "pr"])]
+    #[arg(help = "Don't set the new branch's upstream, regardless of branch.autoSetupMerge")]
+    pub no_track: bool,
+
+    #[arg(long, conflicts_with_all = ["branch_name", "pr"])]
+    #[arg(help = "Reset the new branch to the start point if it already exists")]
+    pub force_branch: bool,
+
+    #[arg(long, value_name = "PREFIX")]
+    #[arg(help = "Prefix for the new branch's name [default: new.branch_prefix from .wt.toml]")]
+    pub branch_prefix: Option<String>,
+
+    #[arg(long, value_name = "SUBDIR")]
+    #[arg(
+        help = "Put the worktree in a subdirectory of the project root [default: new.into from .wt.toml]"
+    )]
+    pub into: Option<PathBuf>,
+
+    #[arg(long, conflicts_with = "into")]
+    #[arg(
+        help = "Put the worktree next to the current worktree instead of where the layout would"
+    )]
+    pub beside_current: bool,
+
     #[arg(short, long, value_name = "PATH")]
-    #[arg(help = "Additional files to symlink into the new worktree")]
+    #[arg(
+        help = "Additional files to symlink into the new worktree, or glob patterns relative to the main worktree"
+    )]
     #[arg(value_parser = file_exists)]
     pub symlinks: Vec<PathBuf>,
+
+    #[arg(long = "hardlink", value_name = "PATH")]
+    #[arg(help = "Additional files to hardlink into the new worktree")]
+    pub hardlinks: Vec<PathBuf>,
+
+    #[arg(short, long, value_name = "DIR")]
+    #[arg(
+        help = "Directory whose contents are copied into the new worktree [default: new.template from .wt.toml]"
+    )]
+    pub template: Option<PathBuf>,
+
+    #[arg(short, long)]
+    #[arg(help = "Overwrite files in the new worktree with files from the template")]
+    pub force: bool,
+
+    #[arg(long)]
+    #[arg(
+        help = "Turn an existing directory into the worktree, keeping its files over checked-out ones (see --help)"
+    )]
+    pub reuse_dir: bool,
+
+    #[arg(long)]
+    #[arg(
+        help = "Give the new worktree its own copy of the repository's hooks [default: new.copy_hooks from .wt.toml]"
+    )]
+    pub copy_hooks: bool,
+
+    #[arg(long, value_name = "CMD", num_args = 0..=1, default_missing_value = "")]
+    #[arg(help = "Open the new worktree in an editor [default: $VISUAL or $EDITOR]")]
+    pub editor: Option<String>,
+
+    #[arg(long)]
+    #[arg(help = "Open the new worktree in a new tmux window")]
+    pub tmux: bool,
+
+    /// Whether to answer yes to confirmation prompts, set from the global `--yes` flag
+    #[arg(skip)]
+    pub yes: bool,
 }
 
 fn file_exists(p: &str) -> Result<PathBuf, Error> {
     PathBuf::from_str(p).context("cannot symlink item, doesn't exist: {p}")
 }
 
-/// Creates a new worktree in the project
-#[instrument]
-pub fn new(args: &New) -> Result<PathBuf, Error> {
+/// The results of creating a new worktree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewOutcome {
+    /// The path to the new worktree
+    pub path: PathBuf,
+    /// The branch checked out in the new worktree
+    pub branch: String,
+    /// Whether the branch was created for the new worktree
+    pub created_branch: bool,
+    /// Template files that weren't copied because the worktree already had them
+    pub skipped_template_files: Vec<PathBuf>,
+    /// Glob patterns passed to `--symlinks` that didn't match anything
+    pub unmatched_symlink_patterns: Vec<String>,
+    /// Files from a reused directory that were kept in place of the checked-out files at the
+    /// same paths
+    pub replaced_files: Vec<PathBuf>,
+    /// Whether no worktree was created because the branch was already checked out in the
+    /// worktree at `path`
+    pub existing: bool,
+}
+
+impl New {
+    /// Returns the name of the worktree, which is the name of the branch when it isn't given
+    pub fn worktree_name(&self) -> Result<&str, Error> {
+        match self.name.as_deref() {
+            Some(name) => Ok(name),
+            None => match self.branch_name.as_deref().or(self.new_branch.as_deref()) {
+                Some(branch) => Ok(branch),
+                None => bail!("a worktree name is required without --branch-name or --new-branch"),
+            },
+        }
+    }
+}
+
+/// Creates a new worktree in the project containing `repo_path`
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn new(args: &New, repo_path: &Path) -> Result<NewOutcome, WtError> {
+    let repos = ProjectRepos::discover(repo_path).context("couldn't locate main worktree")?;
+    Ok(new_in(args, &repos)?)
+}
+
+/// Creates a new worktree in the project of the already discovered `repos`
+#[instrument(skip(repos))]
+pub(crate) fn new_in(args: &New, repos: &ProjectRepos) -> Result<NewOutcome, Error> {
     let current_dir = std::env::current_dir().context("couldn't get current directory")?;
-    let main_worktree = get_main_worktree(&current_dir).context("couldn't locate main worktree")?;
-    let main_wt_path = main_worktree
-        .work_dir()
-        .context("main worktree had no working directory")?;
-    let new_wt_path = new_worktree_path(&main_worktree, &args.name)?;
-    let (branch, needs_creating) = new_worktree_branch_name(args);
+    let main_worktree = &repos.main;
+    let main_wt_path =
+        worktree_or_git_dir(main_worktree).context("couldn't get path of main worktree")?;
+    let config = load_config(main_worktree)?;
+    let dir_name = worktree_dir_name(args.worktree_name()?, config.new.dir_names)?;
+    let new_wt_path = if args.beside_current {
+        beside_current_path(repos, dir_name)?
+    } else {
+        let into = args.into.as_deref().or(config.new.into.as_deref());
+        project_worktree_path(main_worktree, dir_name, into)?
+    };
+    let prefix = args
+        .branch_prefix
+        .as_deref()
+        .or(config.new.branch_prefix.as_deref());
+    let (branch, needs_creating) = new_worktree_branch_name(args, prefix);
     if needs_creating {
-        create_branch(main_wt_path, &branch)?;
+        validate_branch_name(&branch)?;
+        if args.force_branch {
+            if let Some(existing) = worktree_with_branch(main_worktree, &branch)? {
+                bail!(
+                    "can't reset branch '{branch}' because it's checked out in {}",
+                    existing.display()
+                );
+            }
+        }
+    } else {
+        if let Some(existing) = worktree_with_branch(main_worktree, &branch)? {
+            return redirect_to_existing(existing, branch, args.yes);
+        }
     }
-    new_worktree(main_wt_path, &new_wt_path, branch)?;
-    for src_path in &args.symlinks {
-        let full_path = if src_path.is_absolute() {
-            src_path.clone()
-        } else {
-            current_dir.join(src_path)
+    // Catch this before there's a half-finished worktree to clean up
+    if let Some(dir) = args
+        .hardlinks
+        .iter()
+        .map(|path| current_dir.join(path))
+        .find(|path| path.is_dir())
+    {
+        bail!(
+            "can't hardlink {} because it's a directory, use --symlinks for directories",
+            dir.display()
+        );
+    }
+    let _lock = ProjectLock::acquire(&project_root(main_worktree)?)?;
+    let set_aside = if !new_wt_path.exists() {
+        None
+    } else if args.reuse_dir {
+        Some(set_aside_contents(&new_wt_path)?)
+    } else {
+        return Err(WtError::WorktreeExists(new_wt_path).into());
+    };
+    if let Some(parent) = new_wt_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("couldn't create directory: {}", parent.display()))?;
+    }
+    let created = (|| {
+        if let Some(pr) = args.pr {
+            fetch_pull_request(main_worktree, &main_wt_path, pr, &branch)?;
+        } else if needs_creating {
+            let track = match (args.set_upstream, args.no_track) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            return new_worktree_with_branch(
+                &main_wt_path,
+                &new_wt_path,
+                &branch,
+                args.from.as_deref(),
+                track,
+                args.force_branch,
+            );
+        }
+        new_worktree(&main_wt_path, &new_wt_path, &branch)
+    })();
+    // The files go back whether or not the worktree was created, so that a failure leaves the
+    // directory as it was
+    let replaced_files = match set_aside {
+        Some(stash) => restore_contents(&stash, &new_wt_path).with_context(|| {
+            format!(
+                "couldn't move the directory's files back, they're in {}",
+                stash.display()
+            )
+        })?,
+        None => Vec::new(),
+    };
+    created?;
+    let new_wt = gix::open(&new_wt_path).context("couldn't open new worktree")?;
+    record_creation_time(new_wt.git_dir())?;
+    let mut unmatched_symlink_patterns = Vec::new();
+    if !args.symlinks.is_empty() || !args.hardlinks.is_empty() {
+        let base =
+            symlink_base_path(repos).context("couldn't determine which worktree to link from")?;
+        let mut symlink_targets = Vec::new();
+        for src_path in &args.symlinks {
+            let pattern = src_path.to_string_lossy();
+            if is_glob(&pattern) {
+                let matches = expand_symlink_pattern(&base, &pattern)
+                    .with_context(|| format!("couldn't expand symlink pattern '{pattern}'"))?;
+                if matches.is_empty() {
+                    unmatched_symlink_patterns.push(pattern.to_string());
+                }
+                symlink_targets.extend(matches);
+            } else {
+                symlink_targets.push(current_dir.join(src_path));
+            }
+        }
+        for target in symlink_targets {
+            let link = link_path(&base, &new_wt_path, &target)?;
+            symlink(&target, &link)
+                .with_context(|| format!("couldn't symlink {}", target.display()))?;
+        }
+        for src_path in &args.hardlinks {
+            let target = current_dir.join(src_path);
+            let link = link_path(&base, &new_wt_path, &target)?;
+            std::fs::hard_link(&target, &link)
+                .with_context(|| format!("couldn't hardlink {}", target.display()))?;
+        }
+    }
+    if args.copy_hooks || config.new.copy_hooks {
+        let hooks_dir = match config.new.hooks_dir {
+            Some(ref dir) => project_root(main_worktree)?.join(dir),
+            None => main_worktree.common_dir().join("hooks"),
         };
-        let suffix = full_path.strip_prefix(
-            worktree_path(&main_worktree).context("couldn't get path of main worktree")?,
-        )?;
-        let symlink_path = new_wt_path.join(suffix);
-        std::os::unix::fs::symlink(full_path, symlink_path)?;
+        copy_hooks(&main_wt_path, &new_wt_path, &hooks_dir)
+            .with_context(|| format!("couldn't copy hooks from {}", hooks_dir.display()))?;
     }
-    Ok(new_wt_path)
+    // The template given on the command line is relative to where `wt` was run, while the one in
+    // the config is relative to the project root
+    let template = match (&args.template, &config.new.template) {
+        (Some(template), _) => Some(current_dir.join(template)),
+        (None, Some(template)) => Some(project_root(main_worktree)?.join(template)),
+        (None, None) => None,
+    };
+    let skipped_template_files = match template {
+        Some(template) => copy_dir_contents(&template, &new_wt_path, args.force)
+            .with_context(|| format!("couldn't copy template: {}", template.display()))?,
+        None => Vec::new(),
+    };
+    Ok(NewOutcome {
+        path: new_wt_path,
+        branch,
+        created_branch: needs_creating,
+        skipped_template_files,
+        unmatched_symlink_patterns,
+        replaced_files,
+        existing: false,
+    })
 }
 
-/// Computes the path for the new worktree given the main worktree and the new worktree name
-#[instrument(skip(main_wt, name), fields(main_wt = traceable_path(main_wt.path()), name = name.as_ref()))]
-pub fn new_worktree_path(main_wt: &Repository, name: impl AsRef<str>) -> Result<PathBuf, Error> {
-    let main_wt_path = worktree_path(main_wt).context("couldn't get main worktree path")?;
-    let new_path = main_wt_path
+/// Describes the branch of a newly created worktree and how to get to it, for people who are
+/// watching rather than capturing the path
+pub fn format_next_steps(outcome: &NewOutcome) -> String {
+    let verb = if outcome.created_branch {
+        "created branch"
+    } else {
+        "checked out branch"
+    };
+    let name = outcome
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    format!(
+        "{verb} '{}'\ncd into it with: cd \"$(wt which {})\"\n",
+        outcome.branch,
+        shell_words::quote(&name)
+    )
+}
+
+/// Offers to use the worktree that already has the branch checked out instead of failing
+///
+/// Without a way to ask, this fails with an error pointing at the existing worktree.
+fn redirect_to_existing(
+    existing: PathBuf,
+    branch: String,
+    assume_yes: bool,
+) -> Result<NewOutcome, Error> {
+    let msg = format!(
+        "Branch '{branch}' is already checked out in {}, use that worktree instead?",
+        existing.display()
+    );
+    if !can_confirm(assume_yes) || !confirm(&msg, assume_yes)? {
+        return Err(WtError::BranchCheckedOut(branch, existing).into());
+    }
+    Ok(NewOutcome {
+        path: existing,
+        branch,
+        created_branch: false,
+        skipped_template_files: Vec::new(),
+        unmatched_symlink_patterns: Vec::new(),
+        replaced_files: Vec::new(),
+        existing: true,
+    })
+}
+
+/// Fetches the head of a GitHub pull request from `origin` into a new local branch
+fn fetch_pull_request(
+    main_wt: &Repository,
+    main_wt_path: &Path,
+    pr: u32,
+    branch: &str,
+) -> Result<(), Error> {
+    let url = remote_url(main_wt, "origin")
+        .context("can't check out a pull request without an 'origin' remote")?;
+    if !is_github_url(&url) {
+        bail!("--pr only supports GitHub pull requests, but origin is {url}");
+    }
+    let refspec = format!("refs/pull/{pr}/head:refs/heads/{branch}");
+    fetch_refspec(main_wt_path, "origin", &refspec).map_err(|err| {
+        if err.to_string().contains("couldn't find remote ref") {
+            anyhow!("pull request #{pr} doesn't exist on {url}")
+        } else {
+            anyhow::Error::from(err).context(format!("couldn't fetch pull request #{pr}"))
+        }
+    })
+}
+
+/// Returns whether the remote URL points at GitHub, in either its https or ssh form
+fn is_github_url(url: &str) -> bool {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = without_scheme
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('@')
+        .next()
+        .unwrap_or_default();
+    host == "github.com"
+}
+
+/// Recursively copies the contents of one directory into another, e.g. a template into a worktree
+///
+/// Files that already exist in the destination are only overwritten if `overwrite` is set,
+/// otherwise their paths relative to the destination are returned.
+fn copy_dir_contents(src: &Path, dest: &Path, overwrite: bool) -> Result<Vec<PathBuf>, Error> {
+    if !src.is_dir() {
+        bail!("not a directory: {}", src.display());
+    }
+    let mut skipped = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(rel_dir) = dirs.pop() {
+        std::fs::create_dir_all(dest.join(&rel_dir))?;
+        for entry in std::fs::read_dir(src.join(&rel_dir))? {
+            let entry = entry?;
+            let rel_path = rel_dir.join(entry.file_name());
+            if entry.path().is_dir() {
+                dirs.push(rel_path);
+                continue;
+            }
+            let dest_path = dest.join(&rel_path);
+            if dest_path.exists() && !overwrite {
+                skipped.push(rel_path);
+                continue;
+            }
+            debug!(path = traceable_path(&rel_path), "copying file");
+            std::fs::copy(entry.path(), &dest_path)
+                .with_context(|| format!("couldn't copy {}", entry.path().display()))?;
+        }
+    }
+    skipped.sort();
+    Ok(skipped)
+}
+
+/// Moves the contents of a directory that's about to become a worktree into a sibling directory,
+/// since git only creates worktrees in empty directories
+///
+/// The returned directory is kept even if `wt` fails so that nothing in it is lost.
+fn set_aside_contents(dir: &Path) -> Result<PathBuf, Error> {
+    if !dir.is_dir() {
+        bail!("can't reuse {} because it isn't a directory", dir.display());
+    }
+    if dir.join(".git").exists() {
+        bail!(
+            "can't reuse {} because it's already a git repository or worktree",
+            dir.display()
+        );
+    }
+    let parent = dir.parent().context("directory has no parent")?;
+    let stash = tempfile::Builder::new()
+        .prefix(".wt-reuse-")
+        .tempdir_in(parent)
+        .context("couldn't create a directory to set files aside in")?
+        .into_path();
+    debug!(
+        path = traceable_path(&stash),
+        "setting aside directory contents"
+    );
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        std::fs::rename(entry.path(), stash.join(entry.file_name()))
+            .with_context(|| format!("couldn't move {}", entry.path().display()))?;
+    }
+    Ok(stash)
+}
+
+/// Moves files that were set aside back into the directory they came from, then removes the
+/// directory they were kept in
+///
+/// Directories are merged with ones that exist in `dest`, while files replace the ones that
+/// exist, whose paths relative to `dest` are returned.
+fn restore_contents(stash: &Path, dest: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut replaced = Vec::new();
+    // Directories are removed after their contents, so they're visited again once emptied
+    let mut dirs = vec![(PathBuf::new(), false)];
+    while let Some((rel_dir, emptied)) = dirs.pop() {
+        if emptied {
+            std::fs::remove_dir(stash.join(&rel_dir))?;
+            continue;
+        }
+        dirs.push((rel_dir.clone(), true));
+        for entry in std::fs::read_dir(stash.join(&rel_dir))? {
+            let entry = entry?;
+            let rel_path = rel_dir.join(entry.file_name());
+            let dest_path = dest.join(&rel_path);
+            let is_dir = entry.file_type()?.is_dir();
+            if is_dir && dest_path.is_dir() && !dest_path.is_symlink() {
+                dirs.push((rel_path, false));
+                continue;
+            }
+            if dest_path.is_dir() && !dest_path.is_symlink() {
+                std::fs::remove_dir_all(&dest_path)?;
+                replaced.push(rel_path.clone());
+            } else if dest_path.symlink_metadata().is_ok() {
+                std::fs::remove_file(&dest_path)?;
+                replaced.push(rel_path.clone());
+            }
+            std::fs::rename(entry.path(), &dest_path)
+                .with_context(|| format!("couldn't move {}", entry.path().display()))?;
+        }
+    }
+    replaced.sort();
+    Ok(replaced)
+}
+
+/// Copies the hooks into the worktree's own git directory and points the worktree at them
+///
+/// Worktrees normally share the hooks of the main repository, so this enables per-worktree
+/// configuration in order to set `core.hooksPath` for just the new worktree.
+fn copy_hooks(main_wt_path: &Path, wt_path: &Path, hooks_dir: &Path) -> Result<(), Error> {
+    let wt = gix::open(wt_path).context("couldn't open new worktree")?;
+    let dest = wt.git_dir().join("hooks");
+    copy_dir_contents(hooks_dir, &dest, true)?;
+    enable_worktree_config(main_wt_path)?;
+    Ok(set_worktree_config(
+        wt_path,
+        "core.hooksPath",
+        &dest.to_string_lossy(),
+    )?)
+}
+
+/// Opens the worktree in an editor, waiting for the editor to exit
+///
+/// An empty `cmd` means the editor named by `$VISUAL` or `$EDITOR` is used.
+pub fn open_in_editor(cmd: &str, wt_path: &Path) -> Result<(), WtError> {
+    let cmd = if cmd.is_empty() {
+        editor_from_env().context("no editor specified and neither $VISUAL nor $EDITOR is set")?
+    } else {
+        cmd.to_string()
+    };
+    let words = shell_words::split(&cmd).with_context(|| format!("invalid editor: {cmd}"))?;
+    let Some((program, args)) = words.split_first() else {
+        return Err(anyhow!("editor command is empty").into());
+    };
+    let status = Command::new(program)
+        .args(args)
+        .arg(wt_path)
+        .current_dir(wt_path)
+        .status()
+        .with_context(|| format!("couldn't launch editor: {cmd}"))?;
+    if !status.success() {
+        return Err(anyhow!("editor exited with {status}").into());
+    }
+    Ok(())
+}
+
+/// Returns the user's preferred editor
+fn editor_from_env() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+}
+
+/// Returns the path for a worktree in the same directory as the current worktree
+fn beside_current_path(repos: &ProjectRepos, name: impl AsRef<str>) -> Result<PathBuf, Error> {
+    let Some(current_wt_path) = repos.current_worktree_path() else {
+        bail!("--beside-current has to be used from inside a worktree");
+    };
+    let path = current_wt_path
         .parent()
-        .ok_or(anyhow!("main worktree had no parent"))?
+        .ok_or(anyhow!("current worktree had no parent"))?
         .join(name.as_ref());
     debug!(
-        path = traceable_path(&new_path),
-        "determined new worktree location"
+        path = traceable_path(&path),
+        "determined location beside current worktree"
     );
-    Ok(new_path)
+    Ok(path)
+}
+
+/// Returns the worktree that symlinked files are expected to live in
+///
+/// This is the main worktree, unless the main repository is bare, in which case it's the current
+/// worktree.
+fn symlink_base_path(repos: &ProjectRepos) -> Result<PathBuf, Error> {
+    let path = match repos.main.work_dir() {
+        Some(path) => path,
+        None => worktree_path(&repos.current).context("couldn't get path of current worktree")?,
+    };
+    Ok(path.to_path_buf())
+}
+
+/// Returns where in the new worktree to link `target` from the worktree at `base`, creating the
+/// link's parent directory if needed
+fn link_path(base: &Path, new_wt_path: &Path, target: &Path) -> Result<PathBuf, Error> {
+    let suffix = target.strip_prefix(base).with_context(|| {
+        format!(
+            "can't link {}, it isn't inside {}",
+            target.display(),
+            base.display()
+        )
+    })?;
+    let link = new_wt_path.join(suffix);
+    if let Some(parent) = link.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("couldn't create directory: {}", parent.display()))?;
+    }
+    Ok(link)
+}
+
+/// Returns whether the path contains glob metacharacters
+pub(crate) fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Expands a glob pattern like `.env*` or `config/*.local` into the matching paths under `base`
+///
+/// A directory that matches is returned as a whole without searching inside it, and only
+/// patterns containing `**` are matched against paths deeper than the pattern itself. The
+/// repository's `.git` is never searched.
+fn expand_symlink_pattern(base: &Path, pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    let max_depth = if pattern.contains("**") {
+        usize::MAX
+    } else {
+        pattern.split('/').count()
+    };
+    let mut matches = Vec::new();
+    let mut to_visit = vec![(base.to_path_buf(), 1)];
+    while let Some((dir, depth)) = to_visit.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            let path = entry.path();
+            let relative = path.strip_prefix(base)?.to_string_lossy().to_string();
+            if gix::glob::wildmatch(
+                pattern.into(),
+                relative.as_str().into(),
+                gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+            ) {
+                matches.push(path);
+            } else if entry.file_type()?.is_dir() && depth < max_depth {
+                to_visit.push((path, depth + 1));
+            }
+        }
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Creates a symlink at `link` pointing to `target`, which may be a file or a directory
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Creates a symlink at `link` pointing to `target`, which may be a file or a directory
+///
+/// Windows has different kinds of symlinks for files and directories, and a directory reached
+/// through a file symlink can't be opened.
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+/// Turns the worktree name into the name of its directory
+///
+/// The branch created for the worktree is always named after the unmodified worktree name.
+fn worktree_dir_name(name: &str, mode: DirNames) -> Result<String, Error> {
+    let is_separator = |c: char| c == '/' || c == std::path::MAIN_SEPARATOR;
+    if !name.contains(is_separator) {
+        return Ok(name.to_string());
+    }
+    match mode {
+        DirNames::Slugify => Ok(name
+            .split(is_separator)
+            .filter(|part| !part.is_empty())
+            .join("-")),
+        DirNames::Error => bail!(
+            "worktree name '{name}' contains a path separator, pick a different name or \
+             change new.dir_names in .wt.toml"
+        ),
+        DirNames::Nest => Ok(name.to_string()),
+    }
 }
 
 /// Determines the branch name and whether it needs to be created
-fn new_worktree_branch_name(args: &New) -> (String, bool) {
+///
+/// The prefix is only applied to branches named after the directory, since explicitly named
+/// branches are used as-is.
+fn new_worktree_branch_name(args: &New, prefix: Option<&str>) -> (String, bool) {
     if let Some(ref branch_name) = args.branch_name {
         debug!(
             branch = branch_name.as_str(),
@@ -93,30 +704,44 @@ fn new_worktree_branch_name(args: &New) -> (String, bool) {
         );
         (new_branch.clone(), true)
     } else {
+        // Without a branch the name is required, so it's always present here
+        let name = args.name.as_deref().unwrap_or_default();
+        let branch = format!("{}{name}", prefix.unwrap_or_default());
         debug!(
-            branch = args.name.as_str(),
+            branch = branch.as_str(),
             "will make new branch with directory name"
         );
-        (args.name.clone(), true)
+        (branch, true)
     }
 }
 
 #[cfg(test)]
 mod test {
 
-    use crate::commands::init::{init, Init};
+    use crate::{
+        commands::{
+            clone::{init_via_clone, Clone},
+            init::{init, Init},
+        },
+        config::{Layout, CONFIG_FILE_NAME},
+        git::{
+            create_branch, create_initial_commit, current_branch_name, get_main_worktree, git_in,
+            upstream_branch,
+        },
+    };
 
     use super::*;
 
     #[test]
     fn branch_name_only_dir_given() {
         let args = New {
-            name: "dir_name".to_string(),
+            name: Some("dir_name".to_string()),
             branch_name: None,
             new_branch: None,
             symlinks: vec![],
+            ..Default::default()
         };
-        let (branch, needs_creating) = new_worktree_branch_name(&args);
+        let (branch, needs_creating) = new_worktree_branch_name(&args, None);
         assert_eq!(branch, "dir_name");
         assert!(needs_creating);
     }
@@ -124,12 +749,13 @@ mod test {
     #[test]
     fn branch_name_existing_branch() {
         let args = New {
-            name: "dir_name".to_string(),
+            name: Some("dir_name".to_string()),
             branch_name: Some("existing_branch".to_string()),
             new_branch: None,
             symlinks: vec![],
+            ..Default::default()
         };
-        let (branch, needs_creating) = new_worktree_branch_name(&args);
+        let (branch, needs_creating) = new_worktree_branch_name(&args, None);
         assert_eq!(branch, "existing_branch");
         assert!(!needs_creating);
     }
@@ -137,29 +763,854 @@ mod test {
     #[test]
     fn branch_name_new_branch() {
         let args = New {
-            name: "dir_name".to_string(),
+            name: Some("dir_name".to_string()),
             branch_name: None,
             new_branch: Some("new_branch".to_string()),
             symlinks: vec![],
+            ..Default::default()
         };
-        let (branch, needs_creating) = new_worktree_branch_name(&args);
+        let (branch, needs_creating) = new_worktree_branch_name(&args, None);
         assert_eq!(branch, "new_branch");
         assert!(needs_creating);
     }
 
+    #[test]
+    fn branch_name_prefix() {
+        let args = New {
+            name: Some("dir_name".to_string()),
+            ..Default::default()
+        };
+        let (branch, needs_creating) = new_worktree_branch_name(&args, Some("alice/"));
+        assert_eq!(branch, "alice/dir_name");
+        assert!(needs_creating);
+        let args = New {
+            name: Some("dir_name".to_string()),
+            new_branch: Some("new_branch".to_string()),
+            ..Default::default()
+        };
+        let (branch, _) = new_worktree_branch_name(&args, Some("alice/"));
+        assert_eq!(branch, "new_branch");
+    }
+
+    #[test]
+    fn derives_dir_name_from_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let proj = temp_dir.path().join("test_proj");
+        let outcome = new(
+            &New {
+                new_branch: Some("feature/login".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.path, proj.join("feature-login"));
+        assert_eq!(outcome.branch, "feature/login");
+        assert!(outcome.created_branch);
+
+        create_branch(&main_wt_path, "fix/123").unwrap();
+        let outcome = new(
+            &New {
+                branch_name: Some("fix/123".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.path, proj.join("fix-123"));
+        assert!(!outcome.created_branch);
+
+        assert!(new(&New::default(), &main_wt_path).is_err());
+    }
+
+    #[test]
+    fn slugifies_dir_names() {
+        assert_eq!(
+            worktree_dir_name("feature/login", DirNames::Slugify).unwrap(),
+            "feature-login"
+        );
+        assert_eq!(
+            worktree_dir_name("fix/123", DirNames::Slugify).unwrap(),
+            "fix-123"
+        );
+        assert_eq!(
+            worktree_dir_name("plain", DirNames::Slugify).unwrap(),
+            "plain"
+        );
+        assert!(worktree_dir_name("fix/123", DirNames::Error).is_err());
+        assert_eq!(
+            worktree_dir_name("fix/123", DirNames::Nest).unwrap(),
+            "fix/123"
+        );
+    }
+
+    #[test]
+    fn opens_editor_in_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let marker = temp_dir.path().join("marker");
+        let cmd = format!("sh -c 'pwd > {}' sh", marker.display());
+        open_in_editor(&cmd, temp_dir.path()).unwrap();
+        let pwd = std::fs::read_to_string(&marker).unwrap();
+        assert_eq!(
+            Path::new(pwd.trim()).canonicalize().unwrap(),
+            temp_dir.path().canonicalize().unwrap()
+        );
+        assert!(open_in_editor("false", temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn recognizes_github_urls() {
+        assert!(is_github_url("https://github.com/org/repo.git"));
+        assert!(is_github_url("git@github.com:org/repo.git"));
+        assert!(is_github_url("ssh://git@github.com/org/repo"));
+        assert!(!is_github_url("https://gitlab.com/org/repo.git"));
+        assert!(!is_github_url("https://github.com.evil.example/org/repo"));
+        assert!(!is_github_url("/srv/git/repo"));
+    }
+
+    #[test]
+    fn pr_requires_github_origin() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let args = New {
+            name: Some("pr-1".into()),
+            pr: Some(1),
+            ..Default::default()
+        };
+        let err = new(&args, &main_wt_path).unwrap_err();
+        assert!(err.to_string().contains("'origin' remote"));
+        git_in(
+            &main_wt_path,
+            &["remote", "add", "origin", "https://gitlab.com/org/repo.git"],
+        );
+        let err = new(&args, &main_wt_path).unwrap_err();
+        assert!(err.to_string().contains("only supports GitHub"));
+    }
+
+    #[test]
+    fn tracks_start_point_when_asked() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let upstream = temp_dir.path().join("upstream");
+        std::fs::create_dir(&upstream).unwrap();
+        gix::init(&upstream).unwrap();
+        create_initial_commit(&upstream).unwrap();
+        let clone_dir = temp_dir.path().join("clones");
+        std::fs::create_dir(&clone_dir).unwrap();
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: upstream.to_string_lossy().to_string(),
+                path: Some(clone_dir),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let default_branch = main_wt_path.file_name().unwrap().to_string_lossy();
+        let start_point = format!("origin/{default_branch}");
+        let tracked = new(
+            &New {
+                name: Some("tracked".into()),
+                from: Some(start_point.clone()),
+                set_upstream: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            upstream_branch(&tracked.path).unwrap(),
+            Some(start_point.clone())
+        );
+        let untracked = new(
+            &New {
+                name: Some("untracked".into()),
+                from: Some(start_point),
+                no_track: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(upstream_branch(&untracked.path).unwrap(), None);
+    }
+
     #[test]
     fn worktree_path() {
         let temp_dir = tempfile::tempdir().unwrap();
         let main_wt_path = init(&Init {
             name: "test_proj".into(),
             path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
         })
         .unwrap();
         let main_wt = gix::open(main_wt_path).unwrap();
-        let new_wt_path = new_worktree_path(&main_wt, "new_wt").unwrap();
+        let new_wt_path = project_worktree_path(&main_wt, "new_wt", None).unwrap();
         assert_eq!(
             new_wt_path,
             temp_dir.path().join("test_proj").join("new_wt")
         );
     }
+
+    #[test]
+    fn worktree_path_into_subdir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let main_wt = gix::open(main_wt_path).unwrap();
+        let new_wt_path =
+            project_worktree_path(&main_wt, "new_wt", Some(Path::new("worktrees"))).unwrap();
+        assert_eq!(
+            new_wt_path,
+            temp_dir
+                .path()
+                .join("test_proj")
+                .join("worktrees")
+                .join("new_wt")
+        );
+        assert!(
+            project_worktree_path(&main_wt, "new_wt", Some(Path::new("../elsewhere"))).is_err()
+        );
+    }
+
+    #[test]
+    fn worktree_path_grouped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            layout: Some(Layout::Grouped),
+            ..Default::default()
+        })
+        .unwrap();
+        let proj = temp_dir.path().join("test_proj");
+        assert_eq!(main_wt_path, proj.join("worktrees").join("trunk"));
+        let main_wt = gix::open(&main_wt_path).unwrap();
+        assert_eq!(project_root(&main_wt).unwrap(), proj);
+        assert_eq!(
+            project_worktree_path(&main_wt, "other", None).unwrap(),
+            proj.join("worktrees").join("other")
+        );
+        assert_eq!(
+            project_worktree_path(&main_wt, "new_wt", None).unwrap(),
+            proj.join("worktrees").join("new_wt")
+        );
+        // `into` is still relative to the project root
+        assert_eq!(
+            project_worktree_path(&main_wt, "new_wt", Some(Path::new("other"))).unwrap(),
+            proj.join("other").join("new_wt")
+        );
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.path, proj.join("worktrees").join("feature"));
+    }
+
+    #[test]
+    fn rejects_invalid_branch_names_before_creating_anything() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["has space", "fix~1", "a..b", "x.lock"] {
+            let err = new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap_err();
+            assert!(
+                err.to_string()
+                    .starts_with(&format!("invalid branch name '{name}': ")),
+                "{err}"
+            );
+            assert!(!temp_dir.path().join("test_proj").join(name).exists());
+        }
+    }
+
+    #[test]
+    fn creates_worktree_beside_current() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            layout: Some(Layout::Grouped),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("a".into()),
+                into: Some("elsewhere".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("b".into()),
+                beside_current: true,
+                ..Default::default()
+            },
+            &outcome.path,
+        )
+        .unwrap();
+        assert_eq!(
+            outcome.path,
+            temp_dir
+                .path()
+                .join("test_proj")
+                .join("elsewhere")
+                .join("b")
+        );
+    }
+
+    #[test]
+    fn suggests_next_steps() {
+        let outcome = NewOutcome {
+            path: PathBuf::from("/proj/my feature"),
+            branch: "alice/my-feature".into(),
+            created_branch: true,
+            skipped_template_files: Vec::new(),
+            unmatched_symlink_patterns: Vec::new(),
+            replaced_files: Vec::new(),
+            existing: false,
+        };
+        assert_eq!(
+            format_next_steps(&outcome),
+            "created branch 'alice/my-feature'\ncd into it with: cd \"$(wt which 'my feature')\"\n"
+        );
+    }
+
+    #[test]
+    fn worktree_path_bare() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            bare: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let main_wt = get_main_worktree(main_wt_path).unwrap();
+        let new_wt_path = project_worktree_path(&main_wt, "new_wt", None).unwrap();
+        assert_eq!(
+            new_wt_path,
+            temp_dir.path().join("test_proj").join("new_wt")
+        );
+    }
+
+    #[test]
+    fn reports_new_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("new_wt".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            outcome,
+            NewOutcome {
+                path: temp_dir.path().join("test_proj").join("new_wt"),
+                branch: "new_wt".into(),
+                created_branch: true,
+                skipped_template_files: vec![],
+                unmatched_symlink_patterns: vec![],
+                replaced_files: vec![],
+                existing: false,
+            }
+        );
+    }
+
+    #[test]
+    fn redirects_to_worktree_with_branch_checked_out() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("other".into()),
+                branch_name: Some("trunk".into()),
+                yes: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert!(outcome.existing);
+        assert_eq!(outcome.path, main_wt_path);
+        assert!(!temp_dir.path().join("test_proj").join("other").exists());
+    }
+
+    #[test]
+    fn uses_configured_branch_prefix() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let proj = temp_dir.path().join("test_proj");
+        std::fs::write(
+            proj.join(CONFIG_FILE_NAME),
+            "[new]\nbranch_prefix = \"alice/\"\n",
+        )
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.path, proj.join("feature"));
+        assert_eq!(outcome.branch, "alice/feature");
+        let outcome = new(
+            &New {
+                name: Some("other".into()),
+                branch_prefix: Some("bob/".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.branch, "bob/other");
+    }
+
+    #[test]
+    fn keeps_slashes_in_branch_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature/login".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            outcome.path,
+            temp_dir.path().join("test_proj").join("feature-login")
+        );
+        assert_eq!(outcome.branch, "feature/login");
+    }
+
+    #[test]
+    fn refuses_existing_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let args = New {
+            name: Some("feature".into()),
+            ..Default::default()
+        };
+        new(&args, &main_wt_path).unwrap();
+        let err = new(&args, &main_wt_path).unwrap_err();
+        assert_eq!(err.exit_code(), 3);
+    }
+
+    #[test]
+    fn symlinks_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let fixtures = main_wt_path.join("fixtures");
+        std::fs::create_dir(&fixtures).unwrap();
+        std::fs::write(fixtures.join("data.json"), "{}").unwrap();
+        let outcome = new(
+            &New {
+                name: Some("new_wt".into()),
+                symlinks: vec![fixtures.clone()],
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let link = outcome.path.join("fixtures");
+        assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        assert!(link.is_dir());
+        assert_eq!(
+            std::fs::read_to_string(link.join("data.json")).unwrap(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn expands_symlink_patterns() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path();
+        for dir in ["config/nested", ".git"] {
+            std::fs::create_dir_all(base.join(dir)).unwrap();
+        }
+        for file in [
+            ".env",
+            ".env.local",
+            "README",
+            "config/app.local",
+            "config/nested/db.local",
+            ".git/.env.git",
+        ] {
+            std::fs::write(base.join(file), "").unwrap();
+        }
+        let relative = |pattern| {
+            expand_symlink_pattern(base, pattern)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.strip_prefix(base).unwrap().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(relative(".env*"), vec![".env", ".env.local"]);
+        assert_eq!(relative("config/*.local"), vec!["config/app.local"]);
+        assert_eq!(
+            relative("config/**/*.local"),
+            vec!["config/app.local", "config/nested/db.local"]
+        );
+        assert_eq!(relative("conf*"), vec!["config"]);
+        assert!(relative("*.missing").is_empty());
+    }
+
+    #[test]
+    fn symlinks_glob_matches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        std::fs::create_dir(main_wt_path.join("config")).unwrap();
+        std::fs::write(main_wt_path.join("config").join("app.local"), "local").unwrap();
+        let outcome = new(
+            &New {
+                name: Some("new_wt".into()),
+                symlinks: vec!["config/*.local".into(), "*.missing".into()],
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let link = outcome.path.join("config").join("app.local");
+        assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(outcome.unmatched_symlink_patterns, vec!["*.missing"]);
+    }
+
+    #[test]
+    fn hardlinks_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let env_file = main_wt_path.join(".env");
+        std::fs::write(&env_file, "SECRET=1").unwrap();
+        let outcome = new(
+            &New {
+                name: Some("new_wt".into()),
+                hardlinks: vec![env_file.clone()],
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let link = outcome.path.join(".env");
+        assert!(!link.symlink_metadata().unwrap().file_type().is_symlink());
+        std::fs::write(&link, "SECRET=2").unwrap();
+        assert_eq!(std::fs::read_to_string(&env_file).unwrap(), "SECRET=2");
+
+        let err = new(
+            &New {
+                name: Some("other_wt".into()),
+                hardlinks: vec![main_wt_path.clone()],
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("it's a directory"));
+        assert!(!temp_dir.path().join("test_proj").join("other_wt").exists());
+    }
+
+    #[test]
+    fn resets_existing_branch_with_force_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        git_in(&main_wt_path, &["branch", "release"]);
+        git_in(
+            &main_wt_path,
+            &["commit", "-q", "--allow-empty", "-m", "New commit"],
+        );
+        let mut args = New {
+            name: Some("release".into()),
+            new_branch: Some("release".into()),
+            from: Some("trunk".into()),
+            ..Default::default()
+        };
+        assert!(new(&args, &main_wt_path).is_err());
+
+        args.force_branch = true;
+        let outcome = new(&args, &main_wt_path).unwrap();
+        assert!(outcome.created_branch);
+        assert_eq!(
+            git_in(&main_wt_path, &["rev-parse", "release"]),
+            git_in(&main_wt_path, &["rev-parse", "trunk"])
+        );
+
+        // Resetting the branch would pull it out from under the worktree it's checked out in
+        let err = new(
+            &New {
+                name: Some("other".into()),
+                new_branch: Some("release".into()),
+                force_branch: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("checked out in"), "{err}");
+        assert!(!temp_dir.path().join("test_proj").join("other").exists());
+    }
+
+    #[test]
+    fn failure_leaves_no_branch_behind() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        // git refuses to add a worktree where one is registered but missing, which it only
+        // notices after creating the branch
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&outcome.path).unwrap();
+        let mut args = New {
+            name: Some("feature".into()),
+            new_branch: Some("other".into()),
+            ..Default::default()
+        };
+        assert!(new(&args, &main_wt_path).is_err());
+        let main_wt = gix::open(&main_wt_path).unwrap();
+        assert!(main_wt.find_reference("refs/heads/other").is_err());
+
+        // A branch reset with --force-branch goes back to where it was
+        create_branch(&main_wt_path, "release").unwrap();
+        let tip = |main_wt: &gix::Repository| {
+            main_wt
+                .find_reference("refs/heads/release")
+                .unwrap()
+                .peel_to_id_in_place()
+                .unwrap()
+                .detach()
+        };
+        let before = tip(&main_wt);
+        create_initial_commit(&main_wt_path).unwrap();
+        args.new_branch = Some("release".into());
+        args.force_branch = true;
+        assert!(new(&args, &main_wt_path).is_err());
+        assert_eq!(tip(&gix::open(&main_wt_path).unwrap()), before);
+    }
+
+    #[test]
+    fn reuses_existing_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        std::fs::write(main_wt_path.join("tracked.txt"), "tracked").unwrap();
+        git_in(&main_wt_path, &["add", "tracked.txt"]);
+        git_in(&main_wt_path, &["commit", "-q", "-m", "add tracked file"]);
+
+        let proj = temp_dir.path().join("test_proj");
+        let scratch = proj.join("feature");
+        std::fs::create_dir_all(scratch.join("notes")).unwrap();
+        std::fs::write(scratch.join("notes").join("todo.md"), "- [ ] start").unwrap();
+        std::fs::write(scratch.join("tracked.txt"), "mine").unwrap();
+        let mut args = New {
+            name: Some("feature".into()),
+            ..Default::default()
+        };
+        let err = new(&args, &main_wt_path).unwrap_err();
+        assert!(matches!(err, WtError::WorktreeExists(_)));
+
+        args.reuse_dir = true;
+        let outcome = new(&args, &main_wt_path).unwrap();
+        assert_eq!(outcome.path, scratch);
+        assert_eq!(outcome.replaced_files, vec![PathBuf::from("tracked.txt")]);
+        assert_eq!(
+            std::fs::read_to_string(scratch.join("notes").join("todo.md")).unwrap(),
+            "- [ ] start"
+        );
+        assert_eq!(
+            std::fs::read_to_string(scratch.join("tracked.txt")).unwrap(),
+            "mine"
+        );
+        let wt = gix::open(&scratch).unwrap();
+        assert_eq!(current_branch_name(&wt).unwrap(), "feature");
+        // Nothing is left behind where the files were set aside
+        let leftovers = std::fs::read_dir(&proj)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(".wt-reuse-")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn copies_template() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        std::fs::write(main_wt_path.join("tracked.txt"), "tracked").unwrap();
+        git_in(&main_wt_path, &["add", "tracked.txt"]);
+        git_in(&main_wt_path, &["commit", "-q", "-m", "add tracked file"]);
+
+        let template = temp_dir.path().join("template");
+        std::fs::create_dir_all(template.join(".vscode")).unwrap();
+        std::fs::write(template.join(".vscode").join("settings.json"), "{}").unwrap();
+        std::fs::write(template.join("tracked.txt"), "from template").unwrap();
+        let mut args = New {
+            name: Some("feature".into()),
+            template: Some(template),
+            ..Default::default()
+        };
+        let outcome = new(&args, &main_wt_path).unwrap();
+        assert_eq!(
+            outcome.skipped_template_files,
+            vec![PathBuf::from("tracked.txt")]
+        );
+        assert!(outcome.path.join(".vscode").join("settings.json").exists());
+        assert_eq!(
+            std::fs::read_to_string(outcome.path.join("tracked.txt")).unwrap(),
+            "tracked"
+        );
+
+        args.name = Some("forced".into());
+        args.force = true;
+        let outcome = new(&args, &main_wt_path).unwrap();
+        assert!(outcome.skipped_template_files.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(outcome.path.join("tracked.txt")).unwrap(),
+            "from template"
+        );
+    }
+
+    #[test]
+    fn copies_hooks_into_worktree_git_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let hooks = main_wt_path.join(".git").join("hooks");
+        std::fs::create_dir_all(&hooks).unwrap();
+        std::fs::write(hooks.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                copy_hooks: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let wt_hooks = main_wt_path
+            .join(".git")
+            .join("worktrees")
+            .join("feature")
+            .join("hooks");
+        assert!(wt_hooks.join("pre-commit").exists());
+        let hooks_path =
+            PathBuf::from(git_in(&outcome.path, &["rev-parse", "--git-path", "hooks"]));
+        assert_eq!(
+            hooks_path.canonicalize().unwrap(),
+            wt_hooks.canonicalize().unwrap()
+        );
+    }
 }
@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::Args;
+use gix::Repository;
+use tracing::{debug, instrument};
+
+use crate::{
+    commands::new::{new_in, New},
+    error::WtError,
+    git::{
+        create_tracking_branch, current_branch_name, get_worktrees, ref_from_branch,
+        worktree_or_git_dir, ProjectRepos,
+    },
+    util::traceable_path,
+    Error,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Populate {
+    #[arg(short, long, value_name = "GLOB")]
+    #[arg(help = "Only create worktrees for branches matching the pattern, e.g. 'release/*'")]
+    pub pattern: Option<String>,
+
+    #[arg(short, long, value_name = "REMOTE", default_value = "origin")]
+    #[arg(help = "The remote whose branches to create worktrees for")]
+    pub remote: String,
+}
+
+/// The result of creating worktrees for a remote's branches
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopulateOutcome {
+    /// The branches that got a worktree, along with the path of the worktree
+    pub created: Vec<(String, PathBuf)>,
+    /// The branches that were skipped because they're already checked out or their worktree
+    /// directory already exists
+    pub skipped: Vec<String>,
+}
+
+/// Creates a worktree with a local tracking branch for each of the remote's branches
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn populate(args: &Populate, repo_path: &Path) -> Result<PopulateOutcome, WtError> {
+    // Every worktree is created from the same repositories, so they're only discovered once
+    let repos = ProjectRepos::discover(repo_path).context("couldn't get main worktree")?;
+    let main_wt = &repos.main;
+    let main_wt_path = worktree_or_git_dir(main_wt)?;
+    let mut checked_out = get_worktrees(main_wt)
+        .context("couldn't get list of worktrees")?
+        .into_iter()
+        .filter_map(|wt| wt.branch)
+        .collect::<Vec<_>>();
+    if let Ok(branch) = current_branch_name(main_wt) {
+        checked_out.push(branch);
+    }
+    let mut outcome = PopulateOutcome::default();
+    for branch in remote_branches(main_wt, &args.remote)? {
+        if let Some(ref pattern) = args.pattern {
+            if !gix::glob::wildmatch(
+                pattern.as_str().into(),
+                branch.as_str().into(),
+                gix::glob::wildmatch::Mode::empty(),
+            ) {
+                continue;
+            }
+        }
+        if checked_out.contains(&branch) {
+            debug!(branch = branch.as_str(), "branch is already checked out");
+            outcome.skipped.push(branch);
+            continue;
+        }
+        let has_local_branch = main_wt
+            .try_find_reference(&ref_from_branch(&branch)?)
+            .with_context(|| format!("couldn't look up branch '{branch}'"))?
+            .is_some();
+        if !has_local_branch {
+            create_tracking_branch(&main_wt_path, &branch, &format!("{}/{branch}", args.remote))?;
+        }
+        let res = new_in(
+            &New {
+                branch_name: Some(branch.clone()),
+                ..Default::default()
+            },
+            &repos,
+        );
+        match res {
+            Ok(created) => outcome.created.push((branch, created.path)),
+            Err(err) if matches!(err.downcast_ref(), Some(WtError::WorktreeExists(_))) => {
+                debug!(
+                    branch = branch.as_str(),
+                    "worktree directory already exists"
+                );
+                outcome.skipped.push(branch);
+            }
+            Err(err) => {
+                return Err(err
+                    .context(format!("couldn't create worktree for branch '{branch}'"))
+                    .into())
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+/// Returns the names of the remote's branches, without the remote prefix, sorted by name
+fn remote_branches(repo: &Repository, remote: &str) -> Result<Vec<String>, Error> {
+    let prefix = format!("refs/remotes/{remote}/");
+    let mut branches = repo
+        .references()
+        .context("couldn't read references")?
+        .remote_branches()
+        .context("couldn't read remote branches")?
+        .filter_map(Result::ok)
+        .filter_map(|r| {
+            r.name()
+                .as_bstr()
+                .to_string()
+                .strip_prefix(&prefix)
+                .map(|branch| branch.to_string())
+        })
+        .filter(|branch| branch != "HEAD")
+        .collect::<Vec<_>>();
+    branches.sort();
+    Ok(branches)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::clone::{init_via_clone, Clone},
+        git::{create_initial_commit, git_in},
+    };
+
+    #[test]
+    fn creates_worktrees_for_remote_branches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let upstream = temp_dir.path().join("upstream");
+        std::fs::create_dir(&upstream).unwrap();
+        gix::init(&upstream).unwrap();
+        create_initial_commit(&upstream).unwrap();
+        for branch in ["feature", "release/1", "release/2"] {
+            git_in(&upstream, &["branch", branch]);
+        }
+        let clone_dir = temp_dir.path().join("clones");
+        std::fs::create_dir(&clone_dir).unwrap();
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: upstream.to_string_lossy().to_string(),
+                path: Some(clone_dir),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let project = main_wt_path.parent().unwrap();
+        let args = Populate {
+            pattern: Some("release/*".into()),
+            remote: "origin".into(),
+        };
+        let outcome = populate(&args, &main_wt_path).unwrap();
+        assert_eq!(
+            outcome.created,
+            vec![
+                ("release/1".to_string(), project.join("release-1")),
+                ("release/2".to_string(), project.join("release-2")),
+            ]
+        );
+        let wt = gix::open(project.join("release-1")).unwrap();
+        assert_eq!(current_branch_name(&wt).unwrap(), "release/1");
+
+        let outcome = populate(
+            &Populate {
+                pattern: None,
+                remote: "origin".into(),
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            outcome.created,
+            vec![("feature".to_string(), project.join("feature"))]
+        );
+        let default_branch = main_wt_path.file_name().unwrap().to_string_lossy();
+        let mut expected_skipped = vec![
+            default_branch.to_string(),
+            "release/1".to_string(),
+            "release/2".to_string(),
+        ];
+        expected_skipped.sort();
+        assert_eq!(outcome.skipped, expected_skipped);
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use itertools::Itertools;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{
+        get_main_worktree, get_worktrees, main_worktree_info, rename_branch, validate_branch_name,
+    },
+    util::traceable_path,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct RenameBranch {
+    #[arg(value_name = "WT_NAME")]
+    pub name: String,
+
+    /// The new name of the worktree's branch
+    #[arg(value_name = "NEW_BRANCH")]
+    pub new_branch: String,
+}
+
+/// Renames the branch checked out in the named worktree of the project containing `repo_path`,
+/// returning the branch's old name
+///
+/// The worktree's directory stays where it is.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn rename_worktree_branch(args: &RenameBranch, repo_path: &Path) -> Result<String, WtError> {
+    validate_branch_name(&args.new_branch)?;
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let mut worktrees = get_worktrees(&main_wt).context("couldn't get list of worktrees")?;
+    worktrees.extend(main_worktree_info(&main_wt)?);
+    let Some(info) = worktrees.iter().find(|wt| wt.name == args.name) else {
+        return Err(anyhow!(
+            "no worktree named '{}', known worktrees:\n{}",
+            args.name,
+            worktrees.iter().map(|wt| &wt.name).sorted().join("\n")
+        )
+        .into());
+    };
+    let Some(ref old) = info.branch else {
+        return Err(anyhow!(
+            "worktree '{}' has a detached HEAD, so there's no branch to rename",
+            args.name
+        )
+        .into());
+    };
+    rename_branch(&info.path, old, &args.new_branch)
+        .with_context(|| format!("couldn't rename branch '{old}' to '{}'", args.new_branch))?;
+    Ok(old.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{
+            init::{init, Init},
+            new::{new, New},
+        },
+        git::{current_branch_name, git_in, upstream_branch},
+    };
+
+    #[test]
+    fn renames_branch_in_place() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        git_in(&outcome.path, &["branch", "--set-upstream-to", "trunk"]);
+
+        let args = RenameBranch {
+            name: "feature".into(),
+            new_branch: "feature/better-name".into(),
+        };
+        assert_eq!(
+            rename_worktree_branch(&args, &main_wt_path).unwrap(),
+            "feature"
+        );
+        let wt = gix::open(&outcome.path).unwrap();
+        assert_eq!(current_branch_name(&wt).unwrap(), "feature/better-name");
+        assert_eq!(
+            upstream_branch(&outcome.path).unwrap().as_deref(),
+            Some("trunk")
+        );
+
+        git_in(&outcome.path, &["checkout", "-q", "--detach"]);
+        let err = rename_worktree_branch(&args, &main_wt_path).unwrap_err();
+        assert!(err.to_string().contains("detached HEAD"));
+    }
+}
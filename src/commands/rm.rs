@@ -1,21 +1,35 @@
-use anyhow::{bail, Context};
-use clap::Args;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use clap::{Args, ValueEnum};
 use itertools::Itertools;
 use tracing::instrument;
 
 use crate::{
+    commands::{lock::describe_reason, new::is_glob},
+    config::load_config,
+    error::WtError,
     git::{
-        branch_from_ref, delete_branch, get_main_worktree, get_worktree_branch_ref, get_worktrees,
-        global_default_branch_name, remove_worktree, sibling_worktree_path,
+        branch_upstream, current_worktree, delete_branch, delete_remote_branch, get_worktrees,
+        is_current_worktree_alias, is_dirty, main_worktree_info, project_root, prune_worktree,
+        ref_from_branch, remove_worktree, ProjectRepos, WorktreeInfo,
     },
+    hooks::run_hook,
+    project_lock::ProjectLock,
+    prompt::{cancellable, confirm, is_interactive, select},
+    util::{closest_matches, paint, traceable_path, Stream, DIRTY_STYLE, EMPHASIS_STYLE},
     Error,
 };
 
-#[derive(Args, Debug, Clone)]
+#[derive(Args, Debug, Clone, Default)]
 pub struct Remove {
     #[arg(value_name = "WT_NAME")]
     pub names: Vec<String>,
 
+    #[arg(short, long, conflicts_with = "names")]
+    #[arg(help = "Remove every worktree except the main one")]
+    pub all: bool,
+
     #[arg(short, long)]
     #[arg(help = "Delete the worktree(s) without requiring confirmation")]
     pub force: bool,
@@ -23,61 +37,926 @@ pub struct Remove {
     #[arg(short('l'), long)]
     #[arg(help = "Don't the branch(es) checked out in the worktree(s)")]
     pub leave_branches: bool,
+
+    #[arg(long, conflicts_with = "leave_branches")]
+    #[arg(help = "Also delete the branch(es) from their upstream remote")]
+    pub delete_remote: bool,
+
+    #[arg(long)]
+    #[arg(help = "Report what would be removed without removing anything")]
+    pub dry_run: bool,
+
+    #[arg(long)]
+    #[arg(help = "Print the path of each removed worktree to stdout, one per line")]
+    pub porcelain: bool,
+
+    #[arg(long)]
+    #[arg(
+        help = "Delete directories in the project left empty by the removal, including the root"
+    )]
+    pub prune_empty: bool,
+
+    #[arg(long, value_enum, value_name = "STATUS", conflicts_with_all = ["names", "all"])]
+    #[arg(help = "Preselect worktrees with this status when choosing interactively")]
+    pub select: Option<Preselect>,
+
+    /// Whether to answer yes to confirmation prompts, set from the global `--yes` flag
+    #[arg(skip)]
+    pub yes: bool,
+}
+
+/// Which worktrees start out selected when choosing worktrees to remove interactively
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preselect {
+    /// Worktrees without uncommitted changes
+    Clean,
+    /// Worktrees with uncommitted changes
+    Dirty,
+}
+
+/// A worktree that was removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedWorktree {
+    /// The name of the worktree
+    pub name: String,
+    /// Where the worktree was
+    pub path: PathBuf,
+    /// Whether the worktree's directory was already gone, so it only had to be pruned
+    pub pruned: bool,
+    /// The branch that was deleted along with the worktree, if any
+    pub deleted_branch: Option<String>,
+    /// The remote branch that was deleted, as `<remote>/<branch>`, if any
+    pub deleted_remote_branch: Option<String>,
+}
+
+/// The results of removing worktrees
+///
+/// When doing a dry run this describes what would have been removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoveOutcome {
+    pub removed: Vec<RemovedWorktree>,
+    /// Worktrees that weren't removed because their `pre_remove` hook failed
+    pub kept: Vec<String>,
+    /// Problems encountered while removing the worktrees
+    pub warnings: Vec<String>,
+    /// Directories deleted with `--prune-empty` because the removal left them empty
+    pub pruned_dirs: Vec<PathBuf>,
 }
 
-/// Remove one or more worktrees
-#[instrument]
-pub fn remove(args: &Remove) -> Result<(), Error> {
-    let main_wt =
-        get_main_worktree(std::env::current_dir().context("couldn't get current directory")?)
-            .context("couldn't get main worktree")?;
+/// Remove one or more worktrees from the project containing `repo_path`
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn remove(args: &Remove, repo_path: &Path) -> Result<RemoveOutcome, WtError> {
+    let repos = ProjectRepos::discover(repo_path).context("couldn't get main worktree")?;
+    let main_wt = &repos.main;
+    // Guesses at what a mistyped name meant are only safe when the user gets to check them
+    let will_confirm = args.dry_run || (!args.force && !args.yes);
+    let mut guesses = Vec::new();
+    // Opening every worktree is slow in big projects, so this is only done once
+    let infos = get_worktrees(main_wt).context("couldn't get list of worktrees")?;
+    // The main worktree is never included, so it can't be selected for removal
+    let worktrees = infos.iter().map(|wt| wt.name.clone()).collect::<Vec<_>>();
+    let main_info = main_worktree_info(main_wt)?;
+    // Paths and `@` may point at the main worktree, which is then refused
+    let all_infos = infos
+        .iter()
+        .chain(main_info.as_ref())
+        .cloned()
+        .collect::<Vec<_>>();
     let to_delete = if args.names.is_empty() {
-        let default_branch = global_default_branch_name().context("couldn't get default branch")?;
-        let worktrees = get_worktrees(&main_wt)
-            .context("couldn't get list of worktrees")?
-            .into_iter()
-            .filter(|name| name != &default_branch)
-            .collect::<Vec<_>>();
         if worktrees.is_empty() {
-            bail!("no other worktrees to remove");
+            return Err(anyhow!("no other worktrees to remove").into());
+        }
+        if args.all {
+            worktrees
+        } else {
+            if !is_interactive() {
+                return Err(anyhow!(
+                    "can't select worktrees interactively because stdin isn't a terminal, pass \
+                     the names of the worktrees to remove or --all instead"
+                )
+                .into());
+            }
+            let defaults = match args.select {
+                Some(select) => {
+                    let dirtiness = infos
+                        .iter()
+                        // A worktree whose directory is gone has no changes left to lose
+                        .map(|wt| is_dirty(&wt.path).unwrap_or(false))
+                        .collect::<Vec<_>>();
+                    preselected(&dirtiness, select)
+                }
+                None => Vec::new(),
+            };
+            cancellable(
+                inquire::MultiSelect::new("Select worktrees to remove", worktrees)
+                    .with_page_size(15)
+                    .with_default(&defaults)
+                    .prompt(),
+                "failed to get selected worktrees",
+            )?
         }
-        inquire::MultiSelect::new("Select worktrees to remove", worktrees)
-            .with_page_size(15)
-            .prompt()
-            .context("failed to get selected worktrees")?
     } else {
-        args.names.clone()
+        let names = args
+            .names
+            .iter()
+            .map(|name| {
+                let wt = if is_current_worktree_alias(name) {
+                    current_worktree(&repos, &all_infos)?
+                } else if is_path(name, &worktrees) {
+                    worktree_at(Path::new(name), &all_infos)?
+                } else {
+                    return Ok(name.clone());
+                };
+                if wt.is_main {
+                    bail!("refusing to remove the main worktree '{}'", wt.name);
+                }
+                Ok(wt.name.clone())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut resolved = Vec::new();
+        for name in expand_patterns(&names, &worktrees)? {
+            let wt_name = resolve_name(&name, &worktrees, main_info.as_ref(), will_confirm)?;
+            if wt_name != name {
+                guesses.push(format!("using '{wt_name}' for '{name}'"));
+            }
+            resolved.push(wt_name);
+        }
+        resolved
     };
+    // Removing the directory out from under the shell would leave it somewhere that doesn't exist
+    let cwd = std::env::current_dir().ok();
+    let inside = |wt: &WorktreeInfo| {
+        let Ok(wt_path) = wt.path.canonicalize() else {
+            return false;
+        };
+        [Some(repo_path), cwd.as_deref()]
+            .into_iter()
+            .flatten()
+            .any(|path| {
+                path.canonicalize()
+                    .is_ok_and(|path| path.starts_with(&wt_path))
+            })
+    };
+    if let Some(wt) = infos
+        .iter()
+        .find(|wt| to_delete.contains(&wt.name) && inside(wt))
+    {
+        return Err(anyhow!(
+            "can't remove worktree '{}' from inside it, run `wt rm {}` from another worktree",
+            wt.name,
+            wt.name
+        )
+        .into());
+    }
     if !args.force {
+        if let Some(info) = infos
+            .iter()
+            .find(|wt| wt.locked && to_delete.contains(&wt.name))
+        {
+            return Err(anyhow!(
+                "worktree '{}' is locked{}, unlock it or use --force to remove it anyway",
+                info.name,
+                describe_reason(&info.lock_reason)
+            )
+            .into());
+        }
+    }
+    if !args.force && !args.dry_run {
+        let targets = to_delete
+            .iter()
+            .filter_map(|name| infos.iter().find(|wt| &wt.name == name))
+            // A worktree whose directory is gone has no changes left to lose
+            .map(|wt| (wt, is_dirty(&wt.path).unwrap_or(false)))
+            .collect::<Vec<_>>();
+        let notes = guesses.iter().map(|guess| format!("{guess}\n")).join("");
         let msg = format!(
-            "Are you sure you want to remove the selected worktrees?\n{}\n",
-            to_delete.iter().join("\n")
+            "{notes}Are you sure you want to remove the selected worktrees?\n{}\n",
+            describe_targets(&targets)
         );
-        let confirm = inquire::Confirm::new(&msg)
-            .with_default(false)
-            .prompt()
-            .context("failed to get confirmation")?;
-        if !confirm {
-            bail!("removal cancelled");
+        if !confirm(&msg, args.yes)? {
+            return Err(WtError::Cancelled("removal cancelled".to_string()));
         }
     }
+    let config = load_config(main_wt)?;
+    let project_root = project_root(main_wt)?;
+    let lock = ProjectLock::acquire(&project_root)?;
+    let mut outcome = RemoveOutcome {
+        warnings: guesses,
+        ..Default::default()
+    };
+    let mut removed_paths = Vec::new();
     for name in &to_delete {
-        let path = sibling_worktree_path(&main_wt, name)
-            .with_context(|| format!("couldn't get path for worktree '{name}'"))?;
-        let repo = gix::open(&path).with_context(|| format!("couldn't open worktree '{name}'"))?;
-        let branch_ref = get_worktree_branch_ref(&repo)
-            .with_context(|| format!("couldn't get branch for worktree '{name}'"))?;
-        let mut msg = format!("removed worktree '{name}'");
-        remove_worktree(path).with_context(|| format!("couldn't remove worktree '{name}'"))?;
-        if !args.leave_branches {
-            let branch_name = branch_from_ref(branch_ref.as_ref())?;
-            // NOTE: you need to delete the branch from the main worktree because looking up the
-            //       ref of the branch will fail in the newly-deleted worktree
-            delete_branch(&main_wt, &branch_ref)
+        let info = infos
+            .iter()
+            .find(|wt| &wt.name == name)
+            .ok_or_else(|| anyhow!("no worktree named '{name}'"))?;
+        if let Some(ref hook) = config.hooks.pre_remove {
+            if !args.dry_run && !info.prunable {
+                if let Err(err) = run_hook("pre_remove", hook, info) {
+                    if !args.force {
+                        outcome
+                            .warnings
+                            .push(format!("kept worktree '{name}': {err:#}"));
+                        outcome.kept.push(name.clone());
+                        continue;
+                    }
+                    outcome.warnings.push(format!("{err:#}"));
+                }
+            }
+        }
+        if !args.dry_run {
+            if info.prunable {
+                // There's no directory for git to remove, only its record of the worktree
+                prune_worktree(main_wt, name)
+                    .with_context(|| format!("couldn't prune worktree '{name}'"))?;
+            } else {
+                remove_worktree(repo_path, &info.path, args.force)
+                    .with_context(|| format!("couldn't remove worktree '{name}'"))?;
+            }
+            removed_paths.push(info.path.clone());
+        }
+        let Some(branch_name) = info.branch.as_ref().filter(|_| !args.leave_branches) else {
+            outcome.removed.push(RemovedWorktree {
+                name: name.clone(),
+                path: info.path.clone(),
+                pruned: info.prunable,
+                deleted_branch: None,
+                deleted_remote_branch: None,
+            });
+            continue;
+        };
+        // The upstream has to be looked up before the branch is gone
+        let upstream = branch_upstream(main_wt, branch_name);
+        if !args.dry_run {
+            // NOTE: you need to delete the branch from the main worktree because looking up
+            //       the ref of the branch will fail in the newly-deleted worktree
+            delete_branch(main_wt, &ref_from_branch(branch_name)?)
                 .with_context(|| format!("couldn't delete branch '{branch_name}'"))?;
-            msg.push_str(format!(" and branch '{branch_name}'").as_str());
         }
-        eprintln!("{}", msg);
+        let deleted_remote_branch = match upstream {
+            Some((remote, remote_branch)) if args.delete_remote => {
+                if !args.dry_run {
+                    delete_remote_branch(repo_path, &remote, &remote_branch).with_context(
+                        || format!("couldn't delete remote branch '{remote}/{remote_branch}'"),
+                    )?;
+                }
+                Some(format!("{remote}/{remote_branch}"))
+            }
+            None if args.delete_remote => {
+                outcome.warnings.push(format!(
+                    "branch '{branch_name}' has no upstream, so there's no remote branch to delete"
+                ));
+                None
+            }
+            _ => None,
+        };
+        outcome.removed.push(RemovedWorktree {
+            name: name.clone(),
+            path: info.path.clone(),
+            pruned: info.prunable,
+            deleted_branch: Some(branch_name.clone()),
+            deleted_remote_branch,
+        });
+    }
+    // The lock file would keep the project root from ever being empty
+    drop(lock);
+    if args.prune_empty {
+        outcome.pruned_dirs = prune_empty_dirs(&project_root, &removed_paths)?;
+    }
+    Ok(outcome)
+}
+
+/// Describes the worktrees about to be removed as `name (branch) [dirty]`, one per line
+fn describe_targets(targets: &[(&WorktreeInfo, bool)]) -> String {
+    targets
+        .iter()
+        .map(|(wt, dirty)| {
+            let mut line = format!(
+                "{} ({})",
+                paint(&wt.name, EMPHASIS_STYLE, Stream::Stderr),
+                wt.branch.as_deref().unwrap_or("detached HEAD")
+            );
+            if *dirty {
+                line.push_str(&format!(
+                    " {}",
+                    paint("[dirty]", DIRTY_STYLE, Stream::Stderr)
+                ));
+            }
+            line
+        })
+        .join("\n")
+}
+
+/// Deletes the empty directories left behind by removed worktrees, working outwards from each
+/// worktree's parent up to and including the project root
+///
+/// Only directories inside the project are considered, and a directory that still has anything in
+/// it stops the search for that worktree.
+fn prune_empty_dirs(project_root: &Path, removed_paths: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut pruned = Vec::new();
+    for path in removed_paths {
+        let mut dir = path.parent();
+        while let Some(current) = dir.filter(|d| d.starts_with(project_root)) {
+            let is_empty = match std::fs::read_dir(current) {
+                Ok(mut entries) => entries.next().is_none(),
+                Err(_) => false,
+            };
+            if !is_empty {
+                break;
+            }
+            std::fs::remove_dir(current).with_context(|| {
+                format!("couldn't delete empty directory: {}", current.display())
+            })?;
+            pruned.push(current.to_path_buf());
+            dir = current.parent();
+        }
+    }
+    Ok(pruned)
+}
+
+/// Expands any glob patterns in `names` into the matching worktree names
+///
+/// Names without glob metacharacters are passed through unchanged, and duplicates are dropped.
+fn expand_patterns(names: &[String], worktrees: &[String]) -> Result<Vec<String>, Error> {
+    let mut expanded: Vec<String> = Vec::new();
+    for name in names {
+        let matches = if is_glob(name) {
+            let matches = worktrees
+                .iter()
+                .filter(|wt| {
+                    gix::glob::wildmatch(
+                        name.as_str().into(),
+                        wt.as_str().into(),
+                        gix::glob::wildmatch::Mode::empty(),
+                    )
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                bail!(
+                    "no worktrees match '{name}', available worktrees: {}",
+                    worktrees.join(", ")
+                );
+            }
+            matches
+        } else {
+            vec![name.clone()]
+        };
+        for m in matches {
+            if !expanded.contains(&m) {
+                expanded.push(m);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Resolves a name that doesn't match any worktree to the worktree the user most likely meant
+///
+/// A single close match is accepted when `will_confirm` says the user gets to check it before
+/// anything is removed, and the user is asked to choose between several close matches. Names
+/// without any close match are an error, as is the name of the main worktree `main_wt`.
+fn resolve_name(
+    name: &str,
+    worktrees: &[String],
+    main_wt: Option<&WorktreeInfo>,
+    will_confirm: bool,
+) -> Result<String, Error> {
+    // A linked worktree may share the main worktree's name, in which case it's the one meant
+    if worktrees.iter().any(|wt| wt == name) {
+        return Ok(name.to_string());
+    }
+    if main_wt.is_some_and(|wt| wt.name == name) {
+        bail!("refusing to remove the main worktree '{name}'");
+    }
+    let mut matches = closest_matches(name, worktrees);
+    match matches.len() {
+        0 => bail!(
+            "no worktree named '{name}', known worktrees:\n{}",
+            worktrees.iter().sorted().join("\n")
+        ),
+        1 if will_confirm => Ok(matches.remove(0)),
+        1 => bail!(
+            "no worktree named '{name}', did you mean '{}'? Nothing was removed",
+            matches[0]
+        ),
+        _ => select(
+            &format!("No worktree named '{name}', did you mean:"),
+            matches,
+        ),
+    }
+}
+
+/// Returns the indices of the worktrees to preselect, given whether each worktree is dirty
+fn preselected(dirtiness: &[bool], select: Preselect) -> Vec<usize> {
+    dirtiness
+        .iter()
+        .positions(|&dirty| dirty == (select == Preselect::Dirty))
+        .collect()
+}
+
+/// Returns whether an argument names a worktree by its path rather than by its name
+///
+/// An argument that's a worktree's name is taken as that name even if there's also a file by that
+/// name in the current directory.
+fn is_path(arg: &str, worktrees: &[String]) -> bool {
+    if worktrees.iter().any(|name| name == arg) || is_glob(arg) {
+        return false;
+    }
+    arg.contains(std::path::is_separator) || Path::new(arg).exists()
+}
+
+/// Returns the worktree whose directory is at `path`, relative to the current directory
+fn worktree_at<'a>(path: &Path, worktrees: &'a [WorktreeInfo]) -> Result<&'a WorktreeInfo, Error> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("no worktree at path {}", path.display()))?;
+    worktrees
+        .iter()
+        .find(|wt| wt.path.canonicalize().ok().as_ref() == Some(&canonical))
+        .ok_or_else(|| anyhow!("{} isn't a worktree in this project", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{
+            init::{init, Init},
+            new::{new, New},
+        },
+        config::Layout,
+        git::{get_worktree_names, git_in, lock_worktree},
+    };
+    use std::path::PathBuf;
+
+    /// Creates a project with the specified worktrees, returning the path to the main worktree
+    fn project_with_worktrees(parent: &Path, names: &[&str]) -> std::path::PathBuf {
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(parent.to_path_buf()),
+            branch: Some("trunk".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in names {
+            new(
+                &New {
+                    name: Some(name.to_string()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        main_wt_path
+    }
+
+    #[test]
+    fn removes_worktree_and_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["feature"]);
+        let outcome = remove(
+            &Remove {
+                names: vec!["feature".into()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            outcome.removed,
+            vec![RemovedWorktree {
+                name: "feature".into(),
+                path: temp_dir.path().join("test_proj").join("feature"),
+                pruned: false,
+                deleted_branch: Some("feature".into()),
+                deleted_remote_branch: None,
+            }]
+        );
+        assert!(!temp_dir.path().join("test_proj").join("feature").exists());
+    }
+
+    #[test]
+    fn refuses_to_remove_main_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &[]);
+        let res = remove(
+            &Remove {
+                names: vec!["trunk".into()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        );
+        assert!(res.is_err());
+        assert!(main_wt_path.exists());
+    }
+
+    #[test]
+    fn removes_worktree_named_like_main_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &[]);
+        let linked = new(
+            &New {
+                name: Some("trunk".into()),
+                new_branch: Some("other".into()),
+                into: Some("wts".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap()
+        .path;
+        assert_eq!(linked.file_name(), main_wt_path.file_name());
+        for name in ["trunk".to_string(), linked.display().to_string()] {
+            let outcome = remove(
+                &Remove {
+                    names: vec![name],
+                    force: true,
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+            assert_eq!(outcome.removed[0].path, linked);
+            assert!(!linked.exists());
+            assert!(main_wt_path.exists());
+            // Put it back to be removed by its path
+            git_in(
+                &main_wt_path,
+                &[
+                    "worktree",
+                    "add",
+                    "-q",
+                    "-b",
+                    "other",
+                    &linked.to_string_lossy(),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn removes_all_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["bar", "foo"]);
+        let outcome = remove(
+            &Remove {
+                all: true,
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let removed = outcome
+            .removed
+            .iter()
+            .map(|r| r.name.as_str())
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(removed, vec!["bar", "foo"]);
+        assert!(main_wt_path.exists());
+        assert!(get_worktree_names(&gix::open(&main_wt_path).unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn expands_glob_patterns() {
+        let worktrees = ["pr-123", "pr-456", "feature"].map(String::from);
+        let names = ["pr-*", "feature", "pr-123"].map(String::from);
+        assert_eq!(
+            expand_patterns(&names, &worktrees).unwrap(),
+            vec!["pr-123", "pr-456", "feature"]
+        );
+        // Literal names are left for the lookup to reject
+        let names = ["missing".to_string()];
+        assert_eq!(
+            expand_patterns(&names, &worktrees).unwrap(),
+            vec!["missing"]
+        );
+        let names = ["fix-*".to_string()];
+        assert!(expand_patterns(&names, &worktrees).is_err());
+    }
+
+    #[test]
+    fn resolves_mistyped_names() {
+        let worktrees = ["feat-login", "docs"].map(String::from);
+        let main_wt = WorktreeInfo {
+            name: "trunk".into(),
+            path: PathBuf::from("/proj/trunk"),
+            branch: Some("trunk".into()),
+            is_main: true,
+            prunable: false,
+            locked: false,
+            lock_reason: None,
+            created_at: None,
+        };
+        let resolve =
+            |name: &str, will_confirm| resolve_name(name, &worktrees, Some(&main_wt), will_confirm);
+        assert_eq!(resolve("feat-logn", true).unwrap(), "feat-login");
+        assert_eq!(resolve("docs", false).unwrap(), "docs");
+        let err = resolve("trunk", true).unwrap_err().to_string();
+        assert!(err.contains("main worktree"), "{err}");
+        // Without a confirmation step nobody would notice the guess before it's removed
+        let err = resolve("feat-logn", false).unwrap_err().to_string();
+        assert!(err.contains("did you mean 'feat-login'"), "{err}");
+        let err = resolve("release", true).unwrap_err().to_string();
+        assert!(err.contains("docs\nfeat-login"), "{err}");
+    }
+
+    #[test]
+    fn unknown_name_removes_nothing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["good"]);
+        let err = remove(
+            &Remove {
+                names: vec!["good".into(), "zzzqqq".into()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("no worktree named 'zzzqqq'"),
+            "{err}"
+        );
+        assert!(temp_dir.path().join("test_proj").join("good").exists());
+        let main_wt = gix::open(&main_wt_path).unwrap();
+        assert!(main_wt.find_reference("refs/heads/good").is_ok());
+    }
+
+    #[test]
+    fn prunes_empty_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = temp_dir.path().join("test_proj");
+        for dir in ["trunk", "wts/a/b", "wts/c"] {
+            std::fs::create_dir_all(project.join(dir)).unwrap();
+        }
+        std::fs::remove_dir(project.join("wts/a/b")).unwrap();
+        let pruned = prune_empty_dirs(&project, &[project.join("wts/a/b")]).unwrap();
+        // `wts` still holds `c`, and the project root still holds the main worktree
+        assert_eq!(pruned, vec![project.join("wts/a")]);
+        std::fs::remove_dir_all(project.join("trunk")).unwrap();
+        std::fs::remove_dir(project.join("wts/c")).unwrap();
+        let pruned = prune_empty_dirs(&project, &[project.join("wts/c")]).unwrap();
+        assert_eq!(pruned, vec![project.join("wts"), project.clone()]);
+        assert!(temp_dir.path().exists());
+    }
+
+    #[test]
+    fn removes_from_inside_another_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".into()),
+            layout: Some(Layout::Grouped),
+            ..Default::default()
+        })
+        .unwrap();
+        for (name, into) in [("a", None), ("b", None), ("c", Some("nested/deep"))] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    into: into.map(PathBuf::from),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let proj = temp_dir.path().join("test_proj");
+        let inside_b = proj.join("worktrees").join("b").join("src");
+        std::fs::create_dir_all(&inside_b).unwrap();
+        let outcome = remove(
+            &Remove {
+                names: vec!["a".into(), "c".into()],
+                force: true,
+                ..Default::default()
+            },
+            &inside_b,
+        )
+        .unwrap();
+        assert_eq!(outcome.removed.len(), 2);
+        assert!(!proj.join("worktrees").join("a").exists());
+        assert!(!proj.join("nested").join("deep").join("c").exists());
+        assert!(inside_b.exists());
+    }
+
+    #[test]
+    fn removes_worktrees_by_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["a", "b", "c"]);
+        let proj = temp_dir.path().join("test_proj");
+        // Paths don't have to be canonical, or match how git recorded them
+        let a = proj.join("b").join("..").join("a");
+        let outcome = remove(
+            &Remove {
+                names: vec![a.display().to_string(), "b".into()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let removed = outcome.removed.iter().map(|wt| wt.name.as_str());
+        assert_eq!(removed.collect::<Vec<_>>(), vec!["a", "b"]);
+        assert!(proj.join("c").exists());
+        let not_a_worktree = remove(
+            &Remove {
+                names: vec![temp_dir.path().display().to_string()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        );
+        assert!(not_a_worktree
+            .unwrap_err()
+            .to_string()
+            .contains("isn't a worktree"));
+        let main = remove(
+            &Remove {
+                names: vec![main_wt_path.display().to_string()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        );
+        assert!(main.unwrap_err().to_string().contains("main worktree"));
+    }
+
+    #[test]
+    fn prunes_worktree_with_deleted_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["feature", "other"]);
+        std::fs::remove_dir_all(temp_dir.path().join("test_proj").join("feature")).unwrap();
+        let outcome = remove(
+            &Remove {
+                names: vec!["feature".into()],
+                force: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert!(outcome.removed[0].pruned);
+        let main_wt = gix::open(&main_wt_path).unwrap();
+        assert_eq!(get_worktree_names(&main_wt).unwrap(), vec!["other"]);
+        assert!(main_wt.find_reference("refs/heads/feature").is_err());
+    }
+
+    #[test]
+    fn refuses_to_remove_current_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["feature"]);
+        let inside = temp_dir
+            .path()
+            .join("test_proj")
+            .join("feature")
+            .join("src");
+        std::fs::create_dir_all(&inside).unwrap();
+        for alias in [".", "@"] {
+            let args = Remove {
+                names: vec![alias.into()],
+                force: true,
+                ..Default::default()
+            };
+            let err = remove(&args, &inside).unwrap_err().to_string();
+            assert!(err.contains("from inside it"), "{err}");
+            let err = remove(&args, &main_wt_path).unwrap_err().to_string();
+            assert!(err.contains("main worktree"), "{err}");
+        }
+        assert!(inside.exists());
+    }
+
+    #[test]
+    fn describes_removal_targets() {
+        let info = |name: &str, branch: Option<&str>| WorktreeInfo {
+            name: name.into(),
+            path: PathBuf::from("/proj").join(name),
+            branch: branch.map(String::from),
+            is_main: false,
+            prunable: false,
+            locked: false,
+            lock_reason: None,
+            created_at: None,
+        };
+        let feature = info("feature", Some("alice/feature"));
+        let scratch = info("scratch", None);
+        assert_eq!(
+            describe_targets(&[(&feature, true), (&scratch, false)]),
+            "feature (alice/feature) [dirty]\nscratch (detached HEAD)"
+        );
+    }
+
+    #[test]
+    fn preselects_by_status() {
+        let dirtiness = [true, false, false, true];
+        assert_eq!(preselected(&dirtiness, Preselect::Clean), vec![1, 2]);
+        assert_eq!(preselected(&dirtiness, Preselect::Dirty), vec![0, 3]);
+    }
+
+    #[test]
+    fn dry_run_removes_nothing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["feature"]);
+        let outcome = remove(
+            &Remove {
+                names: vec!["feature".into()],
+                dry_run: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(outcome.removed.len(), 1);
+        assert!(temp_dir.path().join("test_proj").join("feature").exists());
+        let main_wt = gix::open(&main_wt_path).unwrap();
+        assert!(main_wt.find_reference("refs/heads/feature").is_ok());
+    }
+
+    #[test]
+    fn deletes_remote_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["feature", "local"]);
+        let remote = temp_dir.path().join("remote.git");
+        git_in(temp_dir.path(), &["init", "--bare", "remote.git"]);
+        git_in(
+            &main_wt_path,
+            &["remote", "add", "origin", &remote.to_string_lossy()],
+        );
+        let feature_wt = temp_dir.path().join("test_proj").join("feature");
+        git_in(&feature_wt, &["push", "-u", "origin", "feature"]);
+
+        let outcome = remove(
+            &Remove {
+                names: vec!["feature".into(), "local".into()],
+                force: true,
+                delete_remote: true,
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(
+            outcome.removed[0].deleted_remote_branch.as_deref(),
+            Some("origin/feature")
+        );
+        assert_eq!(outcome.removed[1].deleted_remote_branch, None);
+        assert_eq!(outcome.warnings.len(), 1);
+        let remote_repo = gix::open(&remote).unwrap();
+        assert!(remote_repo.find_reference("refs/heads/feature").is_err());
+    }
+
+    #[test]
+    fn refuses_locked_worktree_without_force() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["feature"]);
+        let wt_path = temp_dir.path().join("test_proj").join("feature");
+        lock_worktree(&main_wt_path, &wt_path, Some("keep me")).unwrap();
+        let mut args = Remove {
+            names: vec!["feature".into()],
+            ..Default::default()
+        };
+        let err = remove(&args, &main_wt_path).unwrap_err();
+        assert!(err.to_string().contains("keep me"));
+        assert!(wt_path.exists());
+        args.force = true;
+        remove(&args, &main_wt_path).unwrap();
+        assert!(!wt_path.exists());
+    }
+
+    #[test]
+    fn failed_pre_remove_hook_keeps_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = project_with_worktrees(temp_dir.path(), &["keep", "remove"]);
+        let proj = temp_dir.path().join("test_proj");
+        std::fs::write(
+            proj.join(".wt.toml"),
+            "[hooks]\npre_remove = 'test \"$WT_NAME\" = remove'\n",
+        )
+        .unwrap();
+        // Skip the confirmation without --force, which would also ignore the failing hook
+        let mut args = Remove {
+            names: vec!["keep".into(), "remove".into()],
+            yes: true,
+            ..Default::default()
+        };
+        let outcome = remove(&args, &main_wt_path).unwrap();
+        assert_eq!(outcome.kept, vec!["keep"]);
+        assert_eq!(outcome.removed.len(), 1);
+        assert!(proj.join("keep").exists());
+        assert!(!proj.join("remove").exists());
+
+        args.names = vec!["keep".into()];
+        args.force = true;
+        let outcome = remove(&args, &main_wt_path).unwrap();
+        assert!(outcome.kept.is_empty());
+        assert!(!proj.join("keep").exists());
     }
-    Ok(())
 }
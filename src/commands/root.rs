@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{get_main_worktree, project_root},
+    util::traceable_path,
+};
+
+/// Returns the root directory of the project containing `repo_path`
+#[instrument(skip_all, fields(repo_path = traceable_path(repo_path)))]
+pub fn root(repo_path: &Path) -> Result<PathBuf, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    Ok(project_root(&main_wt).context("couldn't determine project root")?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::init::{init, Init};
+
+    #[test]
+    fn finds_project_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let nested = main_wt_path.join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(root(&nested).unwrap(), temp_dir.path().join("test_proj"));
+    }
+
+    #[test]
+    fn finds_bare_project_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            bare: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            root(&main_wt_path).unwrap(),
+            temp_dir.path().join("test_proj")
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use anyhow::Context;
+use clap::Args;
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{ahead_behind, get_worktrees, is_dirty, upstream_branch, ProjectRepos, WorktreeInfo},
+    util::{paint, traceable_path, Stream, DIRTY_STYLE},
+    Error,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Status {
+    #[arg(long)]
+    #[arg(help = "Print the statuses as JSON")]
+    pub json: bool,
+}
+
+/// The version of the `--json` output, which is bumped whenever a field is removed or changes
+/// meaning
+pub const STATUS_SCHEMA_VERSION: u32 = 1;
+
+/// The output of `status --json`
+#[derive(Debug, Serialize)]
+struct StatusReport<'a> {
+    schema_version: u32,
+    worktrees: Vec<StatusEntry<'a>>,
+}
+
+/// A single worktree in the output of `status --json`
+///
+/// This is kept separate from [`WorktreeStatus`] so that the schema only changes on purpose.
+#[derive(Debug, Serialize)]
+struct StatusEntry<'a> {
+    name: &'a str,
+    path: &'a Path,
+    branch: Option<&'a str>,
+    dirty: bool,
+    ahead: Option<usize>,
+    behind: Option<usize>,
+    upstream: Option<&'a str>,
+    locked: bool,
+}
+
+/// The state of a worktree relative to its index and upstream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorktreeStatus {
+    #[serde(flatten)]
+    pub info: WorktreeInfo,
+    /// Whether the worktree has uncommitted changes or untracked files
+    pub dirty: bool,
+    /// The upstream of the worktree's branch, if it has one
+    pub upstream: Option<String>,
+    /// How many commits the branch is ahead of its upstream
+    pub ahead: Option<usize>,
+    /// How many commits the branch is behind its upstream
+    pub behind: Option<usize>,
+}
+
+/// Returns the status of every worktree in the project containing `repo_path`, sorted by name
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn status(args: &Status, repo_path: &Path) -> Result<Vec<WorktreeStatus>, WtError> {
+    let repos = ProjectRepos::discover(repo_path).context("couldn't get main worktree")?;
+    let mut worktrees = get_worktrees(&repos.main).context("couldn't get list of worktrees")?;
+    worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(worktrees
+        .into_iter()
+        .map(worktree_status)
+        .collect::<Result<_, Error>>()?)
+}
+
+/// Returns the status of a single worktree
+pub(crate) fn worktree_status(info: WorktreeInfo) -> Result<WorktreeStatus, Error> {
+    let dirty = is_dirty(&info.path)
+        .with_context(|| format!("couldn't get status of worktree '{}'", info.name))?;
+    let upstream = upstream_branch(&info.path)?;
+    let (ahead, behind) = if upstream.is_some() {
+        let (ahead, behind) = ahead_behind(&info.path)?;
+        (Some(ahead), Some(behind))
+    } else {
+        (None, None)
+    };
+    Ok(WorktreeStatus {
+        info,
+        dirty,
+        upstream,
+        ahead,
+        behind,
+    })
+}
+
+/// Formats the statuses as aligned columns of name, branch, dirtiness, and upstream state
+pub fn format_status(statuses: &[WorktreeStatus]) -> String {
+    let name_width = statuses
+        .iter()
+        .map(|s| s.info.name.len())
+        .max()
+        .unwrap_or(0);
+    let branch_width = statuses
+        .iter()
+        .map(|s| branch_label(s).len())
+        .max()
+        .unwrap_or(0);
+    let mut output = String::new();
+    for s in statuses {
+        let dirtiness = if s.dirty {
+            paint("dirty", DIRTY_STYLE, Stream::Stdout)
+        } else {
+            "clean".to_string()
+        };
+        let upstream = match (&s.upstream, s.ahead, s.behind) {
+            (Some(upstream), Some(ahead), Some(behind)) => {
+                format!("{upstream} +{ahead} -{behind}")
+            }
+            _ => "no upstream".to_string(),
+        };
+        output.push_str(&format!(
+            "{:name_width$}  {:branch_width$}  {dirtiness}  {upstream}\n",
+            s.info.name,
+            branch_label(s),
+        ));
+    }
+    output
+}
+
+/// Formats the statuses as a JSON object with the schema version and an array of worktrees
+pub fn format_status_json(statuses: &[WorktreeStatus]) -> Result<String, WtError> {
+    let report = StatusReport {
+        schema_version: STATUS_SCHEMA_VERSION,
+        worktrees: statuses
+            .iter()
+            .map(|s| StatusEntry {
+                name: &s.info.name,
+                path: &s.info.path,
+                branch: s.info.branch.as_deref(),
+                dirty: s.dirty,
+                ahead: s.ahead,
+                behind: s.behind,
+                upstream: s.upstream.as_deref(),
+                locked: s.info.locked,
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&report).context("couldn't serialize statuses")?)
+}
+
+/// Returns the branch of the worktree, or a placeholder if HEAD is detached
+fn branch_label(status: &WorktreeStatus) -> &str {
+    status.info.branch.as_deref().unwrap_or("(detached)")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::{
+        init::{init, Init},
+        new::{new, New},
+    };
+
+    #[test]
+    fn reports_dirty_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["clean_wt", "dirty_wt"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let dirty_wt = temp_dir.path().join("test_proj").join("dirty_wt");
+        std::fs::write(dirty_wt.join("scratch.txt"), "").unwrap();
+
+        let statuses = status(&Status::default(), &main_wt_path).unwrap();
+        assert_eq!(
+            format_status(&statuses),
+            "clean_wt  clean_wt  clean  no upstream\n\
+             dirty_wt  dirty_wt  dirty  no upstream\n"
+        );
+    }
+
+    #[test]
+    fn formats_versioned_json() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let outcome = new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        std::fs::write(outcome.path.join("scratch.txt"), "").unwrap();
+
+        let statuses = status(&Status { json: true }, &main_wt_path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&format_status_json(&statuses).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schema_version": 1,
+                "worktrees": [{
+                    "name": "feature",
+                    "path": outcome.path,
+                    "branch": "feature",
+                    "dirty": true,
+                    "ahead": null,
+                    "behind": null,
+                    "upstream": null,
+                    "locked": false,
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{get_main_worktree, get_worktrees, is_dirty, main_worktree_info, WorktreeInfo},
+    prompt::{cancellable, is_interactive},
+    util::traceable_path,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Switch {}
+
+/// Asks which worktree in the project containing `repo_path` to switch to, returning its path
+///
+/// The prompt is skipped when there's only one worktree to pick.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn switch(_args: &Switch, repo_path: &Path) -> Result<PathBuf, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let mut worktrees = get_worktrees(&main_wt).context("couldn't get list of worktrees")?;
+    worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+    worktrees.extend(main_worktree_info(&main_wt)?);
+    // There's nothing to switch to in a worktree whose directory is gone
+    worktrees.retain(|wt| !wt.prunable);
+    if worktrees.len() == 1 {
+        return Ok(worktrees.remove(0).path);
+    }
+    if worktrees.is_empty() {
+        return Err(anyhow!("no worktrees to switch to").into());
+    }
+    if !is_interactive() {
+        return Err(anyhow!("can't ask which worktree to switch to because stdin isn't a terminal, use `wt which` instead").into());
+    }
+    let dirtiness = worktrees
+        .iter()
+        .map(|wt| is_dirty(&wt.path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let labels = switch_labels(&worktrees, &dirtiness);
+    let choice = cancellable(
+        inquire::Select::new("Switch to worktree", labels)
+            .with_page_size(15)
+            .raw_prompt(),
+        "failed to get selected worktree",
+    )?;
+    Ok(worktrees.remove(choice.index).path)
+}
+
+/// Formats each worktree as an aligned line of name, branch, and whether it's dirty
+fn switch_labels(worktrees: &[WorktreeInfo], dirtiness: &[bool]) -> Vec<String> {
+    let name_width = worktrees.iter().map(|wt| wt.name.len()).max().unwrap_or(0);
+    worktrees
+        .iter()
+        .zip(dirtiness)
+        .map(|(wt, &dirty)| {
+            let branch = wt.branch.as_deref().unwrap_or("(detached)");
+            let mut label = format!("{:name_width$}  [{branch}]", wt.name);
+            if dirty {
+                label.push_str("  dirty");
+            }
+            if wt.is_main {
+                label.push_str("  (main)");
+            }
+            label
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::init::{init, Init};
+
+    #[test]
+    fn labels_worktrees() {
+        let info = |name: &str, branch: Option<&str>, is_main| WorktreeInfo {
+            name: name.into(),
+            path: PathBuf::from(name),
+            branch: branch.map(String::from),
+            is_main,
+            prunable: false,
+            locked: false,
+            lock_reason: None,
+            created_at: None,
+        };
+        let worktrees = [
+            info("feature", Some("alice/feature"), false),
+            info("wip", None, false),
+            info("main", Some("main"), true),
+        ];
+        assert_eq!(
+            switch_labels(&worktrees, &[false, true, false]),
+            vec![
+                "feature  [alice/feature]",
+                "wip      [(detached)]  dirty",
+                "main     [main]  (main)",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_prompt_for_single_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            switch(&Switch::default(), &main_wt_path).unwrap(),
+            main_wt_path
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::{fmt::Display, path::Path};
+
+use anyhow::Context;
+use clap::Args;
+use tracing::{debug, instrument};
+
+use crate::{
+    error::WtError,
+    git::{
+        ahead_behind, fast_forward, fetch_all, get_main_worktree, get_worktrees, is_dirty,
+        upstream_branch, worktree_or_git_dir,
+    },
+    util::traceable_path,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Sync {
+    #[arg(short, long)]
+    #[arg(help = "Fast-forward each worktree's branch to its upstream after fetching")]
+    pub pull: bool,
+}
+
+/// What happened to a worktree during a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStatus {
+    /// Remote-tracking refs were updated, but the worktree was left alone
+    Fetched,
+    /// The worktree's branch was fast-forwarded to its upstream
+    FastForwarded,
+    /// The worktree's branch already contained its upstream
+    UpToDate,
+    /// The worktree's branch and its upstream have both gained commits
+    Diverged,
+    /// The worktree has uncommitted changes so it wasn't touched
+    SkippedDirty,
+    /// The worktree's branch has no upstream
+    NoUpstream,
+}
+
+impl Display for SyncStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SyncStatus::Fetched => "fetched",
+            SyncStatus::FastForwarded => "fast-forwarded",
+            SyncStatus::UpToDate => "up to date",
+            SyncStatus::Diverged => "diverged",
+            SyncStatus::SkippedDirty => "skipped (dirty)",
+            SyncStatus::NoUpstream => "no upstream",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Fetches from the project's remotes and optionally fast-forwards each worktree
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn sync(args: &Sync, repo_path: &Path) -> Result<Vec<(String, SyncStatus)>, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    fetch_all(worktree_or_git_dir(&main_wt)?).context("couldn't fetch from remotes")?;
+    let mut worktrees = get_worktrees(&main_wt).context("couldn't get list of worktrees")?;
+    worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut statuses = vec![];
+    for wt in worktrees {
+        let status = if upstream_branch(&wt.path)?.is_none() {
+            SyncStatus::NoUpstream
+        } else if !args.pull {
+            SyncStatus::Fetched
+        } else if is_dirty(&wt.path)? {
+            // The caller is left to warn about these, since it knows whether output is wanted
+            debug!(worktree = wt.name.as_str(), "skipping dirty worktree");
+            SyncStatus::SkippedDirty
+        } else {
+            match ahead_behind(&wt.path)? {
+                (_, 0) => SyncStatus::UpToDate,
+                (0, _) => {
+                    fast_forward(&wt.path)
+                        .with_context(|| format!("couldn't fast-forward worktree '{}'", wt.name))?;
+                    SyncStatus::FastForwarded
+                }
+                _ => SyncStatus::Diverged,
+            }
+        };
+        statuses.push((wt.name, status));
+    }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::{
+            clone::{init_via_clone, Clone},
+            new::{new, New},
+        },
+        git::{create_initial_commit, git_in},
+    };
+
+    #[test]
+    fn reports_worktree_statuses() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let upstream = temp_dir.path().join("upstream");
+        std::fs::create_dir(&upstream).unwrap();
+        gix::init(&upstream).unwrap();
+        create_initial_commit(&upstream).unwrap();
+        let clone_dir = temp_dir.path().join("clones");
+        std::fs::create_dir(&clone_dir).unwrap();
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: upstream.to_string_lossy().to_string(),
+                path: Some(clone_dir),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let default_branch = main_wt_path.file_name().unwrap().to_string_lossy();
+        let upstream_ref = format!("origin/{default_branch}");
+        for name in ["behind", "dirty", "local"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let project = main_wt_path.parent().unwrap();
+        for name in ["behind", "dirty"] {
+            git_in(
+                &project.join(name),
+                &["branch", "--set-upstream-to", &upstream_ref],
+            );
+        }
+        std::fs::write(project.join("dirty").join("scratch.txt"), "").unwrap();
+        git_in(&upstream, &["commit", "--allow-empty", "-m", "New commit"]);
+
+        let statuses = sync(&Sync { pull: true }, &main_wt_path).unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                ("behind".to_string(), SyncStatus::FastForwarded),
+                ("dirty".to_string(), SyncStatus::SkippedDirty),
+                ("local".to_string(), SyncStatus::NoUpstream),
+            ]
+        );
+    }
+}
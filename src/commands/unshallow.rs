@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use anyhow::Context;
+use clap::Args;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{fetch_full_history, get_main_worktree, worktree_or_git_dir},
+    util::traceable_path,
+};
+
+#[derive(Args, Debug, Clone)]
+pub struct Unshallow {
+    #[arg(long, value_name = "REMOTE", default_value = "origin")]
+    #[arg(help = "The remote to fetch the history from")]
+    pub remote: String,
+
+    #[arg(long)]
+    #[arg(help = "Also fetch the remote's other branches, undoing --single-branch")]
+    pub all_branches: bool,
+}
+
+impl Default for Unshallow {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            all_branches: false,
+        }
+    }
+}
+
+/// Fetches the history that a shallow clone left out for the project containing `repo_path`
+///
+/// Returns whether anything needed fetching, since a project with its full history is left alone
+/// unless other branches were asked for.
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn unshallow(args: &Unshallow, repo_path: &Path) -> Result<bool, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let shallow = main_wt.is_shallow();
+    if !shallow && !args.all_branches {
+        return Ok(false);
+    }
+    let path = worktree_or_git_dir(&main_wt)?;
+    fetch_full_history(&path, &args.remote, shallow, args.all_branches)
+        .with_context(|| format!("couldn't fetch history from '{}'", args.remote))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::clone::{init_via_clone, Clone},
+        git::{create_initial_commit, git_in},
+    };
+
+    fn commit_count(path: &Path) -> usize {
+        git_in(path, &["rev-list", "--count", "HEAD"])
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn deepens_shallow_clone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo_name");
+        std::fs::create_dir(&repo_dir).unwrap();
+        gix::init(&repo_dir).unwrap();
+        create_initial_commit(&repo_dir).unwrap();
+        for i in 0..2 {
+            git_in(
+                &repo_dir,
+                &[
+                    "commit",
+                    "-q",
+                    "--allow-empty",
+                    "-m",
+                    &format!("commit {i}"),
+                ],
+            );
+        }
+
+        let clone_dir = temp_dir.path().join("clone_dir");
+        std::fs::create_dir_all(&clone_dir).unwrap();
+        // Local paths are always cloned in full, so this goes through the file transport
+        let main_wt_path = init_via_clone(
+            &Clone {
+                repo: format!("file://{}", repo_dir.display()),
+                path: Some(clone_dir),
+                depth: Some(1),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        assert!(gix::open(&main_wt_path).unwrap().is_shallow());
+        assert_eq!(commit_count(&main_wt_path), 1);
+
+        assert!(unshallow(&Unshallow::default(), &main_wt_path).unwrap());
+        assert!(!gix::open(&main_wt_path).unwrap().is_shallow());
+        assert_eq!(commit_count(&main_wt_path), 3);
+        assert!(!unshallow(&Unshallow::default(), &main_wt_path).unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use itertools::Itertools;
+use tracing::instrument;
+
+use crate::{
+    error::WtError,
+    git::{get_main_worktree, get_worktrees},
+    util::traceable_path,
+};
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Which {
+    #[arg(value_name = "WT_NAME")]
+    pub name: String,
+}
+
+/// Returns the path of a worktree in the project containing `repo_path`
+#[instrument(skip(repo_path), fields(repo_path = traceable_path(repo_path)))]
+pub fn which(args: &Which, repo_path: &Path) -> Result<PathBuf, WtError> {
+    let main_wt = get_main_worktree(repo_path).context("couldn't get main worktree")?;
+    let worktrees = get_worktrees(&main_wt).context("couldn't get list of worktrees")?;
+    // The registered path is authoritative, since the worktree may not be where `wt` would put it
+    // today, e.g. if it was created with `--into`
+    let Some(path) = worktrees
+        .iter()
+        .find(|wt| wt.name == args.name)
+        .map(|wt| wt.path.clone())
+    else {
+        return Err(anyhow!(
+            "no worktree named '{}', known worktrees:\n{}",
+            args.name,
+            worktrees.iter().map(|wt| &wt.name).sorted().join("\n")
+        )
+        .into());
+    };
+    if !path.exists() {
+        return Err(anyhow!(
+            "worktree '{}' is registered but its directory is missing: {}",
+            args.name,
+            path.display()
+        )
+        .into());
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::{
+        init::{init, Init},
+        new::{new, New},
+    };
+
+    #[test]
+    fn finds_registered_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        new(
+            &New {
+                name: Some("feature".into()),
+                ..Default::default()
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        let path = which(
+            &Which {
+                name: "feature".into(),
+            },
+            &main_wt_path,
+        )
+        .unwrap();
+        assert_eq!(path, temp_dir.path().join("test_proj").join("feature"));
+        assert!(which(
+            &Which {
+                name: "missing".into(),
+            },
+            &main_wt_path,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn finds_worktree_in_subdir_from_another_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".into(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        for name in ["feature", "other"] {
+            new(
+                &New {
+                    name: Some(name.into()),
+                    into: Some("wts".into()),
+                    ..Default::default()
+                },
+                &main_wt_path,
+            )
+            .unwrap();
+        }
+        let wts = temp_dir.path().join("test_proj").join("wts");
+        let path = which(
+            &Which {
+                name: "feature".into(),
+            },
+            &wts.join("other"),
+        )
+        .unwrap();
+        assert_eq!(path, wts.join("feature"));
+    }
+}
@@ -0,0 +1,371 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use gix::Repository;
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::{git::project_root, Error};
+
+/// The name of the project configuration file, which lives in the project root
+pub const CONFIG_FILE_NAME: &str = ".wt.toml";
+
+/// Settings read from the project's [CONFIG_FILE_NAME] and the user's configuration file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub new: NewConfig,
+    pub hooks: HooksConfig,
+    pub projects: ProjectsConfig,
+}
+
+/// Settings for creating new projects, which are only read from the user's configuration file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectsConfig {
+    /// The directory to create projects under when `init` or `clone` isn't given a path
+    pub dir: Option<PathBuf>,
+    /// The initial branch of new projects when git's `init.defaultBranch` isn't set
+    pub default_branch: Option<String>,
+    /// Where the worktrees of new projects are placed within the project directory
+    pub layout: Layout,
+}
+
+/// Where a project's worktrees live relative to the project root
+///
+/// A project's layout is chosen when it's created and recorded in its repository's git config
+/// under [LAYOUT_GIT_KEY], since moving existing worktrees isn't something `wt` does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// Every worktree, including the main one, is directly under the project root
+    #[default]
+    Flat,
+    /// Every worktree, including the main one, is under the [GROUPED_DIR] subdirectory of the
+    /// project root
+    Grouped,
+}
+
+/// The subdirectory of the project root that holds the worktrees of a [Layout::Grouped] project
+pub const GROUPED_DIR: &str = "worktrees";
+
+/// The git config key recording a project's [Layout]
+pub const LAYOUT_GIT_KEY: &str = "wt.layout";
+
+impl Layout {
+    /// Returns the directory that holds the worktrees of a project rooted at `project_root`
+    pub fn worktrees_dir(self, project_root: &Path) -> PathBuf {
+        match self {
+            Layout::Flat => project_root.to_path_buf(),
+            Layout::Grouped => project_root.join(GROUPED_DIR),
+        }
+    }
+
+    /// The value recorded under [LAYOUT_GIT_KEY]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Layout::Flat => "flat",
+            Layout::Grouped => "grouped",
+        }
+    }
+}
+
+/// Commands run with `sh` at points in a worktree's lifecycle
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run in a worktree before it's removed
+    pub pre_remove: Option<String>,
+}
+
+/// Settings for creating new worktrees
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NewConfig {
+    /// Prepended to the names of branches created for new worktrees
+    pub branch_prefix: Option<String>,
+    /// What to do with path separators in the names of new worktrees
+    pub dir_names: DirNames,
+    /// The subdirectory of the project root to put new worktrees in
+    pub into: Option<PathBuf>,
+    /// A directory, relative to the project root, whose contents are copied into new worktrees
+    pub template: Option<PathBuf>,
+    /// Whether new worktrees get their own copy of the repository's hooks
+    pub copy_hooks: bool,
+    /// The directory, relative to the project root, to copy hooks from instead of `.git/hooks`
+    pub hooks_dir: Option<PathBuf>,
+}
+
+/// How path separators in worktree names, e.g. from `wt new feature/login`, are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirNames {
+    /// Replace path separators with dashes, e.g. `feature-login`
+    #[default]
+    Slugify,
+    /// Refuse to create the worktree
+    Error,
+    /// Create the worktree in nested directories, e.g. `feature/login`
+    Nest,
+}
+
+/// The keys that can be set in a configuration file
+pub const KEYS: &[&str] = &[
+    "hooks.pre_remove",
+    "new.branch_prefix",
+    "new.copy_hooks",
+    "new.dir_names",
+    "new.hooks_dir",
+    "new.into",
+    "new.template",
+    "projects.default_branch",
+    "projects.dir",
+    "projects.layout",
+];
+
+/// The environment variable that overrides `projects.dir`
+pub const PROJECTS_DIR_VAR: &str = "WT_PROJECTS_DIR";
+
+/// The environment variable that overrides `projects.default_branch`
+pub const DEFAULT_BRANCH_VAR: &str = "WT_DEFAULT_BRANCH";
+
+/// Returns the path of the configuration file for the project whose main worktree is `main_wt`
+pub fn config_path(main_wt: &Repository) -> Result<PathBuf, Error> {
+    Ok(project_root(main_wt)?.join(CONFIG_FILE_NAME))
+}
+
+/// Returns the path of the user's configuration file, which applies to every project
+///
+/// This is `$XDG_CONFIG_HOME/wt/config.toml`, falling back to `~/.config/wt/config.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("wt").join("config.toml"))
+}
+
+/// Returns the directory to create new projects under when no path is given, if one is configured
+///
+/// [PROJECTS_DIR_VAR] takes precedence over `projects.dir` in the user's configuration file, and a
+/// leading `~` in either is expanded to the home directory.
+pub fn default_projects_dir() -> Result<Option<PathBuf>, Error> {
+    let dir = match std::env::var_os(PROJECTS_DIR_VAR).filter(|dir| !dir.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => user_config()?.projects.dir,
+    };
+    Ok(dir.map(|dir| expand_home(&dir)))
+}
+
+/// Where the name of the default branch of new projects came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultBranchSource {
+    /// git's `init.defaultBranch`
+    GitConfig,
+    /// The [DEFAULT_BRANCH_VAR] environment variable
+    Env,
+    /// `projects.default_branch` in the user's configuration file
+    UserConfig,
+    /// Nothing was configured, so it's `main`
+    BuiltIn,
+}
+
+impl std::fmt::Display for DefaultBranchSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefaultBranchSource::GitConfig => write!(f, "init.defaultBranch in git's config"),
+            DefaultBranchSource::Env => write!(f, "${DEFAULT_BRANCH_VAR}"),
+            DefaultBranchSource::UserConfig => write!(f, "projects.default_branch in wt's config"),
+            DefaultBranchSource::BuiltIn => write!(f, "wt's built-in default"),
+        }
+    }
+}
+
+/// Returns the initial branch to use when git's `init.defaultBranch` isn't set, if one is
+/// configured, along with where it was configured
+///
+/// [DEFAULT_BRANCH_VAR] takes precedence over `projects.default_branch` in the user's
+/// configuration file.
+pub fn fallback_default_branch() -> Result<Option<(String, DefaultBranchSource)>, Error> {
+    match std::env::var(DEFAULT_BRANCH_VAR) {
+        Ok(branch) if !branch.is_empty() => Ok(Some((branch, DefaultBranchSource::Env))),
+        _ => Ok(user_config()?
+            .projects
+            .default_branch
+            .map(|branch| (branch, DefaultBranchSource::UserConfig))),
+    }
+}
+
+/// Returns the layout to create new projects with, from `projects.layout` in the user's
+/// configuration file
+pub fn default_layout() -> Result<Layout, Error> {
+    Ok(user_config()?.projects.layout)
+}
+
+/// Reads the user's configuration file, which is empty if it doesn't exist
+fn user_config() -> Result<Config, Error> {
+    match user_config_path() {
+        Some(path) => read_config_file(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Replaces a leading `~` with the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Reads the configuration for the project whose main worktree is `main_wt`
+///
+/// Settings in the project's configuration file take precedence over those in the user's
+/// configuration file, and missing files are treated as empty.
+pub fn load_config(main_wt: &Repository) -> Result<Config, Error> {
+    parse_table(load_table(main_wt)?)
+}
+
+/// Reads the raw contents of the user's and project's configuration files, merged the same way as
+/// [load_config]
+pub fn load_table(main_wt: &Repository) -> Result<Table, Error> {
+    let mut table = match user_config_path() {
+        Some(path) => read_table(&path)?,
+        None => Table::new(),
+    };
+    merge_tables(&mut table, read_table(&config_path(main_wt)?)?);
+    Ok(table)
+}
+
+/// Reads a configuration file, returning the default configuration if it doesn't exist
+pub fn read_config_file(path: &Path) -> Result<Config, Error> {
+    parse_table(read_table(path)?)
+        .with_context(|| format!("invalid config file: {}", path.display()))
+}
+
+/// Reads the raw contents of a configuration file, returning an empty table if it doesn't exist
+pub fn read_table(path: &Path) -> Result<Table, Error> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("couldn't read config file: {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("invalid config file: {}", path.display()))
+}
+
+/// Writes the raw contents of a configuration file, creating its parent directory if needed
+pub fn write_table(path: &Path, table: &Table) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("couldn't create directory: {}", parent.display()))?;
+    }
+    let contents = toml::to_string(table).context("couldn't serialize config")?;
+    std::fs::write(path, contents)
+        .with_context(|| format!("couldn't write config file: {}", path.display()))
+}
+
+/// Validates the raw contents of a configuration file against the known settings
+pub fn parse_table(table: Table) -> Result<Config, Error> {
+    Value::Table(table)
+        .try_into()
+        .context("config doesn't match the known settings")
+}
+
+/// Returns the value of a dotted key like `new.branch_prefix`, if it's set
+pub fn get_value<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Sets the value of a dotted key like `new.branch_prefix`, creating tables as needed
+pub fn set_value(table: &mut Table, key: &str, value: Value) -> Result<(), Error> {
+    let (tables, last) = match key.rsplit_once('.') {
+        Some((tables, last)) => (Some(tables), last),
+        None => (None, key),
+    };
+    let mut current = table;
+    for part in tables.into_iter().flat_map(|t| t.split('.')) {
+        current = current
+            .entry(part)
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .with_context(|| format!("config key '{part}' isn't a table"))?;
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+/// Recursively copies the entries of `overrides` into `base`
+fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(override_table)) => {
+                merge_tables(base_table, override_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_config_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILE_NAME);
+        assert_eq!(read_config_file(&path).unwrap(), Config::default());
+        std::fs::write(&path, "[new]\nbranch_prefix = \"alice/\"\n").unwrap();
+        assert_eq!(
+            read_config_file(&path)
+                .unwrap()
+                .new
+                .branch_prefix
+                .as_deref(),
+            Some("alice/")
+        );
+        std::fs::write(&path, "[new]\nbranch_prefx = \"alice/\"\n").unwrap();
+        assert!(read_config_file(&path).is_err());
+    }
+
+    #[test]
+    fn project_settings_override_user_settings() {
+        let mut base: Table =
+            toml::from_str("[new]\nbranch_prefix = \"a/\"\ninto = \"wts\"\n").unwrap();
+        let overrides: Table = toml::from_str("[new]\nbranch_prefix = \"b/\"\n").unwrap();
+        merge_tables(&mut base, overrides);
+        let config = parse_table(base).unwrap();
+        assert_eq!(config.new.branch_prefix.as_deref(), Some("b/"));
+        assert_eq!(config.new.into, Some(PathBuf::from("wts")));
+    }
+
+    #[test]
+    fn expands_home_dir() {
+        let home = PathBuf::from(std::env::var_os("HOME").unwrap());
+        assert_eq!(expand_home(Path::new("~/src")), home.join("src"));
+        assert_eq!(expand_home(Path::new("~")), home);
+        assert_eq!(expand_home(Path::new("/src/~")), PathBuf::from("/src/~"));
+        assert_eq!(
+            expand_home(Path::new("~alice/src")),
+            PathBuf::from("~alice/src")
+        );
+    }
+
+    #[test]
+    fn gets_and_sets_dotted_keys() {
+        let mut table = Table::new();
+        set_value(&mut table, "new.dir_names", Value::from("nest")).unwrap();
+        assert_eq!(
+            get_value(&table, "new.dir_names"),
+            Some(&Value::from("nest"))
+        );
+        assert_eq!(parse_table(table).unwrap().new.dir_names, DirNames::Nest);
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Failures that scripts may want to tell apart, each of which has its own exit code
+///
+/// This is the error type of the `git` module, the commands, and the functions re-exported at the
+/// crate root. Any other failure is a [WtError::Other], whose exit code is that of the [WtError]
+/// it was caused by, if any.
+///
+/// | Code | Meaning                               |
+/// |------|---------------------------------------|
+/// | 1    | Any other failure                     |
+/// | 2    | Not inside a worktree project         |
+/// | 3    | The worktree already exists           |
+/// | 4    | The user cancelled the operation      |
+/// | 5    | Another `wt` is changing the project  |
+/// | 6    | The branch is checked out elsewhere   |
+/// | 7    | git is missing or too old             |
+/// | 8    | A git command failed                  |
+/// | 9    | The branch name isn't valid           |
+/// | 10   | An identity isn't `Name <email>`      |
+/// | 11   | git has no identity to commit as      |
+/// | 12   | git can't sign commits                |
+/// | 13   | Not inside a worktree                 |
+/// | 14   | A path is outside the project         |
+/// | 15   | The remote didn't respond             |
+#[derive(Debug, thiserror::Error)]
+pub enum WtError {
+    #[error("not inside a worktree project: {}", .0.display())]
+    NotAProject(PathBuf),
+    #[error("worktree already exists: {}", .0.display())]
+    WorktreeExists(PathBuf),
+    #[error("{0}")]
+    Cancelled(String),
+    #[error(
+        "another wt operation is in progress on this project, if there isn't one delete {}",
+        .0.display()
+    )]
+    Busy(PathBuf),
+    #[error("branch '{}' is already checked out in {}", .0, .1.display())]
+    BranchCheckedOut(String, PathBuf),
+    #[error("{0}")]
+    GitUnavailable(String),
+    #[error("{0}")]
+    GitCommandFailed(GitFailure),
+    #[error("invalid branch name '{0}': {1}")]
+    InvalidBranchName(String, String),
+    #[error("identity must look like 'Name <email>': {0}")]
+    InvalidIdentity(String),
+    #[error(
+        "git doesn't know who to make the initial commit as, set it up with `git config --global user.name \"Your Name\"` and `git config --global user.email you@example.com`, or pass --author \"Your Name <you@example.com>\""
+    )]
+    IdentityMissing,
+    #[error("commits can't be signed because {0}")]
+    SigningNotConfigured(String),
+    #[error("there's no current worktree because {} isn't inside one", .0.display())]
+    NotInAWorktree(PathBuf),
+    #[error("worktree subdirectory must be inside the project root: {}", .0.display())]
+    OutsideProject(PathBuf),
+    #[error("no response after {} seconds", .0.as_secs())]
+    RemoteTimedOut(Duration),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for WtError {
+    fn from(err: anyhow::Error) -> Self {
+        // Only a bare WtError is unwrapped, since downcasting one with context would drop the context
+        if err.chain().count() > 1 {
+            return WtError::Other(err);
+        }
+        err.downcast::<WtError>().unwrap_or_else(WtError::Other)
+    }
+}
+
+impl WtError {
+    /// Wraps the error in a [WtError::Other] with `context` explaining what was being done, like
+    /// [anyhow::Error::context]
+    pub fn context<C>(self, context: C) -> WtError
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        WtError::Other(anyhow::Error::from(self).context(context))
+    }
+
+    /// The exit code `wt` should exit with when this error is the cause of a failure
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            WtError::NotAProject(_) => 2,
+            WtError::WorktreeExists(_) => 3,
+            WtError::Cancelled(_) => 4,
+            WtError::Busy(_) => 5,
+            WtError::BranchCheckedOut(..) => 6,
+            WtError::GitUnavailable(_) => 7,
+            WtError::GitCommandFailed(_) => 8,
+            WtError::InvalidBranchName(..) => 9,
+            WtError::InvalidIdentity(_) => 10,
+            WtError::IdentityMissing => 11,
+            WtError::SigningNotConfigured(_) => 12,
+            WtError::NotInAWorktree(_) => 13,
+            WtError::OutsideProject(_) => 14,
+            WtError::RemoteTimedOut(_) => 15,
+            WtError::Other(err) => exit_code(err),
+        }
+    }
+}
+
+/// A git command that exited unsuccessfully, along with everything it printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitFailure {
+    /// The command line, starting with the program
+    pub args: Vec<String>,
+    /// The exit code, which is `None` if git was killed by a signal
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Display for GitFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| shell_words::quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "`{args}` ")?;
+        match self.code {
+            Some(code) => write!(f, "exited with code {code}")?,
+            None => write!(f, "was killed by a signal")?,
+        }
+        // git explains itself on stderr, but a few commands only complain on stdout
+        let details = if self.stderr.is_empty() {
+            &self.stdout
+        } else {
+            &self.stderr
+        };
+        if !details.is_empty() {
+            write!(f, ": {details}")?;
+        }
+        Ok(())
+    }
+}
+
+impl GitFailure {
+    /// Returns whether git failed because the remote wanted credentials that it didn't get or
+    /// didn't accept
+    pub fn is_auth_failure(&self) -> bool {
+        const SIGNS: &[&str] = &[
+            "Authentication failed",
+            "could not read Username",
+            "could not read Password",
+            "terminal prompts disabled",
+            "Permission denied (publickey",
+            "HTTP Basic: Access denied",
+            "The requested URL returned error: 401",
+            "The requested URL returned error: 403",
+        ];
+        SIGNS.iter().any(|sign| self.stderr.contains(sign))
+    }
+}
+
+/// Returns the exit code for an error, which is 1 unless it was caused by a [WtError]
+pub fn exit_code(err: &crate::Error) -> u8 {
+    // Unlike searching `err.chain()`, this also finds errors that were attached as context
+    err.downcast_ref::<WtError>().map_or(1, WtError::exit_code)
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn finds_exit_code_in_context() {
+        let err = std::fs::read("/nonexistent")
+            .context(WtError::NotAProject("/nonexistent".into()))
+            .context("couldn't get main worktree")
+            .unwrap_err();
+        assert_eq!(exit_code(&err), 2);
+        assert_eq!(exit_code(&anyhow::anyhow!("other")), 1);
+        let failure = GitFailure {
+            args: ["git", "worktree", "add", "my dir"]
+                .map(String::from)
+                .to_vec(),
+            code: Some(128),
+            stdout: String::new(),
+            stderr: "fatal: oops".into(),
+        };
+        let err = anyhow::Error::from(WtError::GitCommandFailed(failure))
+            .context("couldn't create worktree");
+        assert_eq!(exit_code(&err), 8);
+        assert_eq!(
+            err.root_cause().to_string(),
+            "`git worktree add 'my dir'` exited with code 128: fatal: oops"
+        );
+    }
+
+    #[test]
+    fn unwraps_bare_errors() {
+        let err = WtError::from(anyhow::Error::from(WtError::Busy("/proj/.wt.lock".into())));
+        assert!(matches!(err, WtError::Busy(_)));
+        // Context would be lost by unwrapping, but the exit code is still found
+        let err = WtError::from(
+            anyhow::Error::from(WtError::Busy("/proj/.wt.lock".into())).context("couldn't lock"),
+        );
+        assert!(matches!(err, WtError::Other(_)));
+        assert_eq!(err.to_string(), "couldn't lock");
+        assert_eq!(err.exit_code(), 5);
+        let err = WtError::from(anyhow::Error::from(WtError::IdentityMissing));
+        assert_eq!(err.context("couldn't init").exit_code(), 11);
+    }
+
+    #[test]
+    fn recognizes_auth_failures() {
+        let failure = |stderr: &str| GitFailure {
+            args: ["git", "clone", "https://example.com/private.git"]
+                .map(String::from)
+                .to_vec(),
+            code: Some(128),
+            stdout: String::new(),
+            stderr: stderr.into(),
+        };
+        assert!(failure(
+            "fatal: could not read Username for 'https://example.com': terminal prompts disabled"
+        )
+        .is_auth_failure());
+        assert!(
+            failure("remote: HTTP Basic: Access denied\nfatal: Authentication failed")
+                .is_auth_failure()
+        );
+        assert!(failure("git@example.com: Permission denied (publickey).").is_auth_failure());
+        assert!(!failure("fatal: unable to access: Could not resolve host").is_auth_failure());
+    }
+}
@@ -1,76 +1,555 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
-use std::{borrow::Cow, path::Path, process::Command};
+use std::{
+    io::Read,
+    path::{Component, Path},
+    process::{Command, Output, Stdio},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use anyhow::{anyhow, bail, Context};
+use anyhow::{anyhow, Context};
 use gix::refs::{FullName, FullNameRef};
 use gix::Repository;
+use serde::Serialize;
 use tracing::debug;
 use tracing::instrument;
 
-use crate::{util::traceable_path, Error};
+use crate::{
+    config::{fallback_default_branch, DefaultBranchSource, Layout, LAYOUT_GIT_KEY},
+    error::{GitFailure, WtError},
+    util::traceable_path,
+};
 const DEFAULT_BRANCH: &str = "main";
+/// The oldest version of git that supports everything `wt` does, i.e. `git worktree remove`
+pub const MIN_GIT_VERSION: (u32, u32) = (2, 17);
 
-/// Returns the global default branch name
-pub fn global_default_branch_name() -> Result<String, Error> {
-    let config = gix::config::File::from_globals().context("couldn't read git config")?;
-    Ok(config
-        .string_by_key("init.defaultBranch")
-        .unwrap_or(Cow::Borrowed(DEFAULT_BRANCH.into()))
-        .to_string())
+/// Returns a command that runs git
+///
+/// Every git command that `wt` runs is made here. git inherits `wt`'s environment untouched, so
+/// settings like `GIT_SSH_COMMAND`, `GIT_CONFIG_GLOBAL`, and `HTTPS_PROXY` apply to it just as
+/// they would to running git by hand, and anything that every command needs is added in one place.
+pub fn git_command() -> Command {
+    Command::new("git")
+}
+
+/// Runs a git command, returning its output if it succeeds
+///
+/// When git exits unsuccessfully the error is a [WtError::GitCommandFailed] holding the command
+/// line, exit code, and everything git printed.
+pub fn run_git(cmd: &mut Command) -> Result<Output, WtError> {
+    let output = cmd
+        .output()
+        .with_context(|| format!("couldn't run `{}`", command_line(cmd)))?;
+    if !output.status.success() {
+        return Err(WtError::GitCommandFailed(git_failure(cmd, &output)));
+    }
+    Ok(output)
+}
+
+/// Runs a git command like [run_git], but with the terminal attached so that git can show its
+/// progress and ask for credentials
+///
+/// git's output goes to stderr rather than being captured, so when it fails the error only has the
+/// command line and exit code, git having already explained itself.
+pub fn run_git_attached(cmd: &mut Command) -> Result<(), WtError> {
+    let status = cmd
+        .stdin(Stdio::inherit())
+        .stdout(std::io::stderr())
+        .stderr(Stdio::inherit())
+        .status()
+        .with_context(|| format!("couldn't run `{}`", command_line(cmd)))?;
+    if !status.success() {
+        let failure = GitFailure {
+            args: std::iter::once(cmd.get_program())
+                .chain(cmd.get_args())
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            code: status.code(),
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+        return Err(WtError::GitCommandFailed(failure));
+    }
+    Ok(())
+}
+
+/// Returns whether the error is from a git command that the remote refused for lack of
+/// credentials
+pub fn is_auth_failure(err: &WtError) -> bool {
+    match err {
+        WtError::GitCommandFailed(failure) => failure.is_auth_failure(),
+        WtError::Other(err) => err.downcast_ref::<WtError>().is_some_and(is_auth_failure),
+        _ => false,
+    }
+}
+
+/// Describes a git command that exited unsuccessfully
+fn git_failure(cmd: &Command, output: &Output) -> GitFailure {
+    GitFailure {
+        args: std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect(),
+        code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    }
+}
+
+/// Runs git in `dir` for a test, panicking if it fails, and returns its trimmed stdout
+#[cfg(test)]
+pub(crate) fn git_in(dir: &Path, args: &[&str]) -> String {
+    let output = run_git(git_command().current_dir(dir).args(args)).unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// Formats a command for display, quoting arguments that need it
+fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_words::quote(&arg.to_string_lossy()).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Checks that git is installed and new enough, returning its `(major, minor)` version
+pub fn check_git() -> Result<(u32, u32), WtError> {
+    let mut cmd = git_command();
+    cmd.arg("--version");
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(WtError::GitUnavailable(
+                "couldn't find git, it must be installed and on your PATH to use wt".to_string(),
+            ));
+        }
+        Err(err) => return Err(anyhow::Error::from(err).context("couldn't run git").into()),
+    };
+    if !output.status.success() {
+        return Err(WtError::GitCommandFailed(git_failure(&cmd, &output)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = parse_git_version(&stdout)
+        .with_context(|| format!("couldn't parse git version: {}", stdout.trim()))?;
+    if version < MIN_GIT_VERSION {
+        return Err(WtError::GitUnavailable(format!(
+            "git {}.{} is too old, wt requires git {}.{} or newer",
+            version.0, version.1, MIN_GIT_VERSION.0, MIN_GIT_VERSION.1
+        )));
+    }
+    Ok(version)
+}
+
+/// Parses the output of `git --version`, e.g. `git version 2.39.3 (Apple Git-145)`
+fn parse_git_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.trim().strip_prefix("git version ")?;
+    let mut parts = version.split(['.', ' ']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Returns the default branch name from `init.defaultBranch`
+///
+/// When a repository is given its local configuration takes precedence over the global
+/// configuration, just like it does for git. Without `init.defaultBranch` this falls back to
+/// `WT_DEFAULT_BRANCH`, then `projects.default_branch` in the user's configuration file, and
+/// finally `main`.
+pub fn default_branch_name(repo: Option<&Repository>) -> Result<String, WtError> {
+    Ok(default_branch_name_with_source(repo)?.0)
+}
+
+/// Returns the default branch name like [default_branch_name], along with where it came from
+pub fn default_branch_name_with_source(
+    repo: Option<&Repository>,
+) -> Result<(String, DefaultBranchSource), WtError> {
+    let name = match repo {
+        Some(repo) => repo
+            .config_snapshot()
+            .string("init.defaultBranch")
+            .map(|name| name.to_string()),
+        None => gix::config::File::from_globals()
+            .context("couldn't read git config")?
+            .string_by_key("init.defaultBranch")
+            .map(|name| name.to_string()),
+    };
+    if let Some(name) = name {
+        return Ok((name, DefaultBranchSource::GitConfig));
+    }
+    match fallback_default_branch()? {
+        Some((name, source)) => {
+            validate_branch_name(&name)
+                .with_context(|| format!("invalid fallback default branch name: {name}"))?;
+            Ok((name, source))
+        }
+        None => Ok((DEFAULT_BRANCH.to_string(), DefaultBranchSource::BuiltIn)),
+    }
+}
+
+/// Checks that the provided name would make a valid branch name
+///
+/// This follows the rules of `git check-ref-format --branch`, and the error says which part of the
+/// name breaks them rather than leaving git to complain about it halfway through creating a
+/// worktree.
+pub fn validate_branch_name(name: impl AsRef<str>) -> Result<(), WtError> {
+    let name = name.as_ref();
+    if let Some(problem) = branch_name_problem(name) {
+        return Err(WtError::InvalidBranchName(name.to_string(), problem));
+    }
+    ref_from_branch(name)?;
+    Ok(())
+}
+
+/// Describes the first way in which a name breaks git's rules for branch names
+fn branch_name_problem(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return Some("branch names can't be empty".to_string());
+    }
+    if name == "@" {
+        return Some("'@' on its own isn't allowed".to_string());
+    }
+    if name.starts_with('-') {
+        return Some("branch names can't start with '-'".to_string());
+    }
+    let chars = name.chars().collect::<Vec<_>>();
+    for (i, c) in chars.iter().enumerate() {
+        let position = i + 1;
+        let next = chars.get(i + 1);
+        match c {
+            c if c.is_ascii_control() => {
+                return Some(format!(
+                    "control character {c:?} at position {position} isn't allowed"
+                ))
+            }
+            ' ' | '~' | '^' | ':' | '?' | '*' | '[' | '\\' => {
+                return Some(format!("'{c}' at position {position} isn't allowed"))
+            }
+            '.' if next == Some(&'.') => {
+                return Some(format!("'..' at position {position} isn't allowed"))
+            }
+            '@' if next == Some(&'{') => {
+                return Some(format!("'@{{' at position {position} isn't allowed"))
+            }
+            '/' if next == Some(&'/') => {
+                return Some(format!(
+                    "consecutive slashes at position {position} aren't allowed"
+                ))
+            }
+            _ => {}
+        }
+    }
+    if name.starts_with('/') || name.ends_with('/') {
+        return Some("branch names can't start or end with '/'".to_string());
+    }
+    if name.ends_with('.') {
+        return Some("branch names can't end with '.'".to_string());
+    }
+    for component in name.split('/') {
+        if component.starts_with('.') {
+            return Some(format!("'{component}' can't start with '.'"));
+        }
+        if component.ends_with(".lock") {
+            return Some(format!("'{component}' can't end with '.lock'"));
+        }
+    }
+    None
 }
 
 /// Creates the initial commit in a repository
 ///
 /// This is necessary for brand new projects to create the main branch
 #[instrument(skip_all, fields(path = traceable_path(repo_path.as_ref())))]
-pub fn create_initial_commit(repo_path: impl AsRef<Path>) -> Result<(), Error> {
-    let mut cmd = Command::new("git");
+pub fn create_initial_commit(repo_path: impl AsRef<Path>) -> Result<(), WtError> {
+    create_initial_commit_with(repo_path, &InitialCommitOptions::default())
+}
+
+/// Stages the specified paths, which are relative to the worktree at `repo_path`
+pub fn add_paths(repo_path: impl AsRef<Path>, paths: &[impl AsRef<Path>]) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .arg("-C")
+            .arg(repo_path.as_ref())
+            .arg("add")
+            .arg("--")
+            .args(paths.iter().map(|p| p.as_ref())),
+    )?;
+    Ok(())
+}
+
+/// How to create the initial commit of a new project
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InitialCommitOptions {
+    /// Sign the commit, which git also does on its own when `commit.gpgSign` is set
+    pub sign: bool,
+    /// Who to make the commit as instead of git's configured identity
+    pub author: Option<Identity>,
+}
+
+/// A git identity like `Jane Doe <jane@example.com>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+impl FromStr for Identity {
+    type Err = WtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s.trim().strip_suffix('>').and_then(|s| s.split_once('<'));
+        let Some((name, email)) = parsed else {
+            return Err(WtError::InvalidIdentity(s.to_string()));
+        };
+        let (name, email) = (name.trim(), email.trim());
+        if name.is_empty() || email.is_empty() || email.contains(['<', '>']) {
+            return Err(WtError::InvalidIdentity(s.to_string()));
+        }
+        Ok(Identity {
+            name: name.to_string(),
+            email: email.to_string(),
+        })
+    }
+}
+
+impl InitialCommitOptions {
+    /// Adds the arguments shared by `git commit` and `git commit-tree`
+    fn apply(&self, cmd: &mut Command) {
+        if self.sign {
+            cmd.arg("-S");
+        }
+        if let Some(ref author) = self.author {
+            cmd.env("GIT_AUTHOR_NAME", &author.name)
+                .env("GIT_AUTHOR_EMAIL", &author.email)
+                .env("GIT_COMMITTER_NAME", &author.name)
+                .env("GIT_COMMITTER_EMAIL", &author.email);
+        }
+    }
+
+    /// Runs the command that creates the commit, explaining failures that are most likely due to
+    /// signing going wrong
+    fn run(&self, cmd: &mut Command) -> Result<Output, WtError> {
+        let res = run_git(cmd);
+        if self.sign {
+            res.map_err(|err| err.context("couldn't sign the initial commit, check that git is set up to sign commits (gpg.format, user.signingKey)"))
+        } else {
+            res
+        }
+    }
+}
+
+/// Creates the initial commit in the repository as described by `opts`
+#[instrument(skip_all, fields(path = traceable_path(repo_path.as_ref())))]
+pub fn create_initial_commit_with(
+    repo_path: impl AsRef<Path>,
+    opts: &InitialCommitOptions,
+) -> Result<(), WtError> {
+    let mut cmd = git_command();
     cmd.arg("-C");
     cmd.arg(repo_path.as_ref());
     cmd.args(["commit", "--allow-empty", "-m", "Initial commit"]);
-    let output = cmd.output()?;
-    if !output.status.success() {
-        bail!("{}", String::from_utf8_lossy(&output.stderr));
+    opts.apply(&mut cmd);
+    opts.run(&mut cmd)?;
+    Ok(())
+}
+
+/// Creates the initial commit on the specified branch of a bare repository
+///
+/// A bare repository has no working directory to commit from, so the commit is created directly
+/// from the empty tree.
+#[instrument(skip_all, fields(path = traceable_path(git_dir.as_ref()), branch = branch.as_ref()))]
+pub fn create_initial_commit_bare(
+    git_dir: impl AsRef<Path>,
+    branch: impl AsRef<str>,
+    opts: &InitialCommitOptions,
+) -> Result<(), WtError> {
+    let git_dir = git_dir.as_ref();
+    let empty_tree = gix::ObjectId::empty_tree(gix::hash::Kind::Sha1);
+    let mut cmd = git_command();
+    cmd.arg("--git-dir")
+        .arg(git_dir)
+        .args(["commit-tree", "-m", "Initial commit"]);
+    opts.apply(&mut cmd);
+    let output = opts.run(cmd.arg(empty_tree.to_string()))?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    run_git(
+        git_command()
+            .arg("--git-dir")
+            .arg(git_dir)
+            .arg("update-ref")
+            .arg(format!("refs/heads/{}", branch.as_ref()))
+            .arg(commit),
+    )?;
+    Ok(())
+}
+
+/// Returns git's config for `repo`, or the global config when the repository doesn't exist yet
+fn config_for(repo: Option<&Repository>) -> Result<gix::config::File<'static>, WtError> {
+    match repo {
+        Some(repo) => Ok(repo.config_snapshot().plumbing().clone()),
+        None => Ok(gix::config::File::from_globals().context("couldn't read git config")?),
+    }
+}
+
+/// Checks that git knows who to make commits in the repository as
+///
+/// git would otherwise fail with an error that doesn't mention how to get past it from `wt`.
+/// Without a repository this checks the global config, so that it can be done before creating one.
+pub fn check_identity_configured(repo: Option<&Repository>) -> Result<(), WtError> {
+    let config = config_for(repo)?;
+    let is_set = |key: &str, vars: &[&str]| {
+        config
+            .string_by_key(key)
+            .is_some_and(|value| !value.is_empty())
+            || vars
+                .iter()
+                .any(|var| std::env::var_os(var).is_some_and(|value| !value.is_empty()))
+    };
+    let has_name = is_set("user.name", &["GIT_AUTHOR_NAME", "GIT_COMMITTER_NAME"]);
+    let has_email = is_set(
+        "user.email",
+        &["GIT_AUTHOR_EMAIL", "GIT_COMMITTER_EMAIL", "EMAIL"],
+    );
+    if !has_name || !has_email {
+        return Err(WtError::IdentityMissing);
+    }
+    Ok(())
+}
+
+/// Returns whether git signs every commit in the repository, i.e. `commit.gpgSign` is set
+///
+/// Without a repository this checks the global config.
+pub fn signs_commits(repo: Option<&Repository>) -> Result<bool, WtError> {
+    Ok(config_for(repo)?
+        .boolean_by_key("commit.gpgsign")
+        .and_then(Result::ok)
+        .unwrap_or(false))
+}
+
+/// Checks that git has the settings it needs to sign commits in the repository
+///
+/// Signing with OpenPGP or X.509 falls back to the committer's identity when there's no signing
+/// key, but SSH signing can't. Without a repository this checks the global config.
+pub fn check_signing_configured(repo: Option<&Repository>) -> Result<(), WtError> {
+    let config = config_for(repo)?;
+    let format = config.string_by_key("gpg.format");
+    let is_ssh = format.as_deref().is_some_and(|format| format == "ssh");
+    let has_key = config
+        .string_by_key("user.signingkey")
+        .is_some_and(|key| !key.is_empty());
+    if is_ssh && !has_key {
+        return Err(WtError::SigningNotConfigured(
+            "gpg.format is 'ssh' but user.signingKey isn't set".to_string(),
+        ));
     }
     Ok(())
 }
 
+/// Points HEAD of a freshly initialized repository at the specified (unborn) branch
+#[instrument(skip_all, fields(branch = branch.as_ref()))]
+pub fn set_unborn_branch(
+    repo_path: impl AsRef<Path>,
+    branch: impl AsRef<str>,
+) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .arg("-C")
+            .arg(repo_path.as_ref())
+            .args(["symbolic-ref", "HEAD"])
+            .arg(format!("refs/heads/{}", branch.as_ref())),
+    )?;
+    Ok(())
+}
+
 /// Creates a new branch in the repository.
 ///
 /// Assumes you're in the project already.
-#[instrument(skip_all, fields(name = name.as_ref()))]
-pub fn create_branch(repo_path: impl AsRef<Path>, name: impl AsRef<str>) -> Result<(), Error> {
-    let output = Command::new("git")
-        .current_dir(&repo_path)
-        .arg("branch")
-        .arg(name.as_ref())
-        .output()?;
-    if !output.status.success() {
-        bail!("{}", String::from_utf8_lossy(&output.stderr));
+pub fn create_branch(repo_path: impl AsRef<Path>, name: impl AsRef<str>) -> Result<(), WtError> {
+    create_branch_from(repo_path, name, None, None, false)
+}
+
+/// Creates a new branch starting at `start_point`, or HEAD if it's `None`
+///
+/// `track` forces whether the branch's upstream is set to the start point, and otherwise git
+/// decides based on `branch.autoSetupMerge`. With `force` a branch that already exists is reset to
+/// the start point, which git refuses to do to a branch that's checked out.
+#[instrument(skip_all, fields(name = name.as_ref(), start_point, track, force))]
+pub fn create_branch_from(
+    repo_path: impl AsRef<Path>,
+    name: impl AsRef<str>,
+    start_point: Option<&str>,
+    track: Option<bool>,
+    force: bool,
+) -> Result<(), WtError> {
+    let mut cmd = git_command();
+    cmd.current_dir(&repo_path).arg("branch");
+    if force {
+        cmd.arg("--force");
     }
+    match track {
+        Some(true) => cmd.arg("--track"),
+        Some(false) => cmd.arg("--no-track"),
+        None => &mut cmd,
+    };
+    cmd.arg(name.as_ref()).args(start_point);
+    run_git(&mut cmd)?;
     Ok(())
 }
 
+/// Creates a branch that tracks the given remote-tracking branch, e.g. `origin/foo`
+pub fn create_tracking_branch(
+    repo_path: impl AsRef<Path>,
+    name: &str,
+    upstream: &str,
+) -> Result<(), WtError> {
+    create_branch_from(repo_path, name, Some(upstream), Some(true), false)
+}
+
 /// Gets the currently checked out branch of the worktree
 #[instrument]
-pub fn get_worktree_branch_ref(repo: &Repository) -> Result<FullName, Error> {
+pub fn get_worktree_branch_ref(repo: &Repository) -> Result<FullName, WtError> {
     repo.head_name()
         .context("couldn't get current branch ref")?
-        .ok_or(anyhow!("worktree had no HEAD"))
+        .ok_or_else(|| anyhow!("worktree had no HEAD").into())
 }
 
 /// Returns the main worktree
 #[instrument(skip_all, fields(starting_path = traceable_path(&starting_path)))]
-pub fn get_main_worktree(starting_path: impl AsRef<Path>) -> Result<Repository, Error> {
-    let starting_path = starting_path.as_ref();
-    let repo = gix::discover(starting_path).context("couldn't determine current repository")?;
-    let main_repo = repo.main_repo().context("couldn't find main worktree")?;
-    debug!(
-        path = traceable_path(main_repo.path()),
-        "found main worktree"
-    );
-    Ok(main_repo)
+pub fn get_main_worktree(starting_path: impl AsRef<Path>) -> Result<Repository, WtError> {
+    Ok(ProjectRepos::discover(starting_path)?.main)
+}
+
+/// The repositories of a project that a command works with, discovered once and then passed
+/// around so that neither has to be found again
+#[derive(Debug, Clone)]
+pub struct ProjectRepos {
+    /// The repository containing the path `wt` was invoked from
+    pub current: Repository,
+    /// The main worktree, or the main repository if it's bare
+    pub main: Repository,
+}
+
+impl ProjectRepos {
+    /// Discovers the repository containing `starting_path` and the main repository of its project
+    #[instrument(skip_all, fields(starting_path = traceable_path(&starting_path)))]
+    pub fn discover(starting_path: impl AsRef<Path>) -> Result<Self, WtError> {
+        let starting_path = starting_path.as_ref();
+        let current = gix::discover(starting_path)
+            .context(WtError::NotAProject(starting_path.to_path_buf()))?;
+        // Only a linked worktree has a git directory separate from the common one
+        let main = if current.git_dir() == current.common_dir() {
+            current.clone()
+        } else {
+            current.main_repo().context("couldn't find main worktree")?
+        };
+        debug!(path = traceable_path(main.path()), "found main worktree");
+        Ok(ProjectRepos { current, main })
+    }
+
+    /// Returns the path of the worktree containing the path `wt` was invoked from
+    pub fn current_worktree_path(&self) -> Option<&Path> {
+        self.current.work_dir()
+    }
 }
 
 /// Creates a new worktree at the specified path, optionally creating a new branch for the worktree
@@ -79,69 +558,675 @@ pub fn new_worktree(
     repo_path: impl AsRef<Path>,
     dir: impl AsRef<Path>,
     branch: impl AsRef<str>,
-) -> Result<(), Error> {
+) -> Result<(), WtError> {
     let dir = dir.as_ref();
     let repo_path = repo_path.as_ref();
-    let mut cmd = Command::new("git");
+    let mut cmd = git_command();
     cmd.current_dir(repo_path);
     cmd.args(["worktree", "add"]).arg(dir).arg(branch.as_ref());
-    let output = cmd.output().context("call to git-worktree failed")?;
+    run_git(&mut cmd)?;
+    Ok(())
+}
+
+/// Creates a new worktree at the specified path along with a new branch for it to check out
+///
+/// The branch starts at `start_point`, or the main worktree's HEAD, and with `force` an existing
+/// branch by that name is reset to it. Both are created by a single git command, but git creates
+/// the branch before checking that it can add the worktree, so when that fails the branch is
+/// deleted again, or put back where it was if it already existed.
+#[instrument(skip_all, fields(dir = dir.as_ref().to_string_lossy().as_ref(), branch = branch.as_ref()))]
+pub fn new_worktree_with_branch(
+    repo_path: impl AsRef<Path>,
+    dir: impl AsRef<Path>,
+    branch: impl AsRef<str>,
+    start_point: Option<&str>,
+    track: Option<bool>,
+    force: bool,
+) -> Result<(), WtError> {
+    let repo_path = repo_path.as_ref();
+    let branch = branch.as_ref();
+    let old_tip = branch_tip(repo_path, branch)?;
+    let mut cmd = git_command();
+    cmd.current_dir(repo_path).args(["worktree", "add"]);
+    match track {
+        Some(true) => cmd.arg("--track"),
+        Some(false) => cmd.arg("--no-track"),
+        None => &mut cmd,
+    };
+    cmd.arg(if force { "-B" } else { "-b" })
+        .arg(branch)
+        .arg(dir.as_ref())
+        .args(start_point);
+    let Err(err) = run_git(&mut cmd) else {
+        return Ok(());
+    };
+    let mut restore = git_command();
+    restore.current_dir(repo_path);
+    match old_tip {
+        Some(ref sha) => restore.args(["update-ref", &format!("refs/heads/{branch}"), sha]),
+        None => restore.args(["branch", "-D", branch]),
+    };
+    // There may be nothing to undo, and either way the original failure is the one to report
+    let _ = run_git(&mut restore);
+    Err(err)
+}
+
+/// Returns the commit the branch points to, or `None` if there's no such branch
+fn branch_tip(repo_path: &Path, branch: &str) -> Result<Option<String>, WtError> {
+    let output = git_command()
+        .current_dir(repo_path)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("refs/heads/{branch}"))
+        .output()
+        .context("call to git-rev-parse failed")?;
     if !output.status.success() {
-        bail!("{}", String::from_utf8_lossy(&output.stderr));
+        return Ok(None);
     }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Forgets a worktree whose directory is gone, like `git worktree prune` but only for that worktree
+pub fn prune_worktree(repo: &Repository, name: &str) -> Result<(), WtError> {
+    let admin_dir = repo.common_dir().join("worktrees").join(name);
+    std::fs::remove_dir_all(&admin_dir)
+        .with_context(|| format!("couldn't delete directory: {}", admin_dir.display()))?;
     Ok(())
 }
 
+/// Returns the output of `git worktree list --porcelain`
+pub fn worktree_list_porcelain(repo_path: impl AsRef<Path>) -> Result<String, WtError> {
+    let output =
+        run_git(
+            git_command()
+                .current_dir(repo_path)
+                .args(["worktree", "list", "--porcelain"]),
+        )?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Removes a worktree from the repository
-pub fn remove_worktree(dir: impl AsRef<Path>) -> Result<(), Error> {
-    let output = Command::new("git")
+///
+/// Locked worktrees are only removed if `unlock` is set.
+pub fn remove_worktree(
+    repo_path: impl AsRef<Path>,
+    dir: impl AsRef<Path>,
+    unlock: bool,
+) -> Result<(), WtError> {
+    let mut cmd = git_command();
+    cmd.current_dir(repo_path)
         .args(["worktree", "remove"])
-        .arg("--force")
-        .arg(dir.as_ref())
-        .output()
-        .context("call to git-worktree failed")?;
-    if !output.status.success() {
-        bail!("{}", String::from_utf8_lossy(&output.stderr));
+        .arg("--force");
+    if unlock {
+        // git requires the flag twice to remove a locked worktree
+        cmd.arg("--force");
     }
+    run_git(cmd.arg(dir.as_ref()))?;
+    Ok(())
+}
+
+/// Locks a worktree so that it can't be pruned, moved, or removed
+pub fn lock_worktree(
+    repo_path: impl AsRef<Path>,
+    dir: impl AsRef<Path>,
+    reason: Option<&str>,
+) -> Result<(), WtError> {
+    let mut cmd = git_command();
+    cmd.current_dir(repo_path).args(["worktree", "lock"]);
+    if let Some(reason) = reason {
+        cmd.args(["--reason", reason]);
+    }
+    run_git(cmd.arg(dir.as_ref()))?;
+    Ok(())
+}
+
+/// Unlocks a locked worktree
+pub fn unlock_worktree(repo_path: impl AsRef<Path>, dir: impl AsRef<Path>) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(repo_path)
+            .args(["worktree", "unlock"])
+            .arg(dir.as_ref()),
+    )?;
+    Ok(())
+}
+
+/// Repairs the links between the repository and its worktrees after the repository has moved
+pub fn repair_worktrees(repo_path: impl AsRef<Path>) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(repo_path)
+            .args(["worktree", "repair"]),
+    )?;
+    Ok(())
+}
+
+/// Allows worktrees to have their own configuration in addition to the repository's
+///
+/// A bare repository's `core.bare` setting is moved into its own per-worktree configuration, as
+/// git requires, since it would otherwise apply to every worktree.
+pub fn enable_worktree_config(repo_path: impl AsRef<Path>) -> Result<(), WtError> {
+    let repo_path = repo_path.as_ref();
+    let git_config = |args: &[&str]| -> Result<String, WtError> {
+        let output = run_git(
+            git_command()
+                .current_dir(repo_path)
+                .arg("config")
+                .args(args),
+        )?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let is_bare =
+        git_config(&["--local", "--type=bool", "--default=false", "core.bare"])? == "true";
+    git_config(&["extensions.worktreeConfig", "true"])?;
+    if is_bare {
+        git_config(&["--unset", "core.bare"])?;
+        git_config(&["--worktree", "core.bare", "true"])?;
+    }
+    Ok(())
+}
+
+/// Sets a configuration value for a single worktree
+///
+/// The repository must have had [enable_worktree_config] called on it first.
+pub fn set_worktree_config(
+    wt_path: impl AsRef<Path>,
+    key: &str,
+    value: &str,
+) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(wt_path)
+            .args(["config", "--worktree", key, value]),
+    )?;
     Ok(())
 }
 
 /// Returns the path of the repo's worktree
-pub fn worktree_path(repo: &Repository) -> Result<&Path, Error> {
-    repo.work_dir()
-        .context("main worktree was a bare repository")
+pub fn worktree_path(repo: &Repository) -> Result<&Path, WtError> {
+    Ok(repo
+        .work_dir()
+        .context("main worktree was a bare repository")?)
 }
 
-/// Returns the path for a sibling worktree
-pub fn sibling_worktree_path(
-    starting_wt: &Repository,
-    name: impl AsRef<str>,
-) -> Result<PathBuf, Error> {
-    let starting_wt_path = worktree_path(starting_wt).context("couldn't get worktree path")?;
-    let new_path = starting_wt_path
+/// Returns the path of the repo's worktree, or of its git directory if the repo is bare
+///
+/// Worktrees of a bare repository are placed alongside its git directory, so this is the path that
+/// other worktree locations are derived from.
+pub fn worktree_or_git_dir(repo: &Repository) -> Result<PathBuf, WtError> {
+    if let Some(path) = repo.work_dir() {
+        return Ok(path.to_path_buf());
+    }
+    // The path of a main repository found via a linked worktree contains `..` components
+    let current_dir = std::env::current_dir().context("couldn't get current directory")?;
+    let path = gix::path::normalize(repo.path().into(), &current_dir)
+        .ok_or(anyhow!("couldn't normalize git directory path"))?;
+    Ok(path.into_owned())
+}
+
+/// Returns the root directory of the project, i.e. the parent directory of the main worktree, or
+/// of the directory holding the worktrees for a [Layout::Grouped] project
+///
+/// For a bare main repository this is the directory containing the repository.
+pub fn project_root(main_wt: &Repository) -> Result<PathBuf, WtError> {
+    let main_wt_path = worktree_or_git_dir(main_wt).context("couldn't get main worktree path")?;
+    let mut root = main_wt_path
         .parent()
-        .ok_or(anyhow!("worktree had no parent"))?
-        .join(name.as_ref());
+        .ok_or(anyhow!("main worktree had no parent"))?;
+    if !main_wt.is_bare() && project_layout(main_wt) == Layout::Grouped {
+        root = root
+            .parent()
+            .ok_or(anyhow!("worktrees directory had no parent"))?;
+    }
+    let root = root.to_path_buf();
+    debug!(path = traceable_path(&root), "determined project root");
+    Ok(root)
+}
+
+/// Returns the layout recorded in the repository's git config, which is [Layout::Flat] for
+/// projects created before layouts were configurable
+pub fn project_layout(repo: &Repository) -> Layout {
+    match repo.config_snapshot().string(LAYOUT_GIT_KEY).as_deref() {
+        Some(value) if value == "grouped" => Layout::Grouped,
+        _ => Layout::Flat,
+    }
+}
+
+/// Records the project's layout in the git config of the repository at `repo_path`
+pub fn set_project_layout(repo_path: impl AsRef<Path>, layout: Layout) -> Result<(), WtError> {
+    run_git(git_command().arg("-C").arg(repo_path.as_ref()).args([
+        "config",
+        LAYOUT_GIT_KEY,
+        layout.as_str(),
+    ]))?;
+    Ok(())
+}
+
+/// Computes where the worktree with the given name belongs in the project whose main worktree is
+/// `main_wt`
+///
+/// Every path `wt` derives for a worktree is anchored at the project root, so this gives the same
+/// answer no matter which worktree it's called from. The worktree is placed alongside the
+/// project's other worktrees, as determined by its layout, unless `into` names a subdirectory of
+/// the project root to put it in.
+#[instrument(skip(main_wt, name), fields(main_wt = traceable_path(main_wt.path()), name = name.as_ref()))]
+pub fn project_worktree_path(
+    main_wt: &Repository,
+    name: impl AsRef<str>,
+    into: Option<&Path>,
+) -> Result<PathBuf, WtError> {
+    let root = project_root(main_wt)?;
+    let mut new_path = project_layout(main_wt).worktrees_dir(&root);
+    if let Some(subdir) = into {
+        new_path = root;
+        if subdir.is_absolute() || subdir.components().any(|c| c == Component::ParentDir) {
+            return Err(WtError::OutsideProject(subdir.to_path_buf()));
+        }
+        new_path.push(subdir);
+    }
+    new_path.push(name.as_ref());
     debug!(
         path = traceable_path(&new_path),
-        "determined sibling worktree location"
+        "determined worktree location"
     );
     Ok(new_path)
 }
 
 /// Deletes the branch from the repository
-pub fn delete_branch(repo: &Repository, branch_ref: &FullName) -> Result<(), Error> {
+pub fn delete_branch(repo: &Repository, branch_ref: &FullName) -> Result<(), WtError> {
     let printable_ref_name = branch_ref.as_bstr();
     let git_ref = repo
         .find_reference(branch_ref.as_ref())
         .with_context(|| format!("couldn't find reference '{printable_ref_name}'"))?;
     git_ref
         .delete()
-        .with_context(|| format!("couldn't delete git reference '{printable_ref_name}'"))
+        .with_context(|| format!("couldn't delete git reference '{printable_ref_name}'"))?;
+    Ok(())
+}
+
+/// Returns the remote and the name of the branch on that remote that `branch` tracks, if any
+pub fn branch_upstream(repo: &Repository, branch: &str) -> Option<(String, String)> {
+    let config = repo.config_snapshot();
+    let remote = config
+        .plumbing()
+        .string_by_key(format!("branch.{branch}.remote").as_str())?
+        .to_string();
+    // A remote of "." means the branch tracks another local branch
+    if remote == "." {
+        return None;
+    }
+    let merge = config
+        .plumbing()
+        .string_by_key(format!("branch.{branch}.merge").as_str())?
+        .to_string();
+    let remote_branch = merge.strip_prefix("refs/heads/").unwrap_or(&merge);
+    Some((remote, remote_branch.to_string()))
+}
+
+/// Returns the URL of the remote, if the remote exists
+pub fn remote_url(repo: &Repository, remote: &str) -> Option<String> {
+    repo.config_snapshot()
+        .plumbing()
+        .string_by_key(format!("remote.{remote}.url").as_str())
+        .map(|url| url.to_string())
+}
+
+/// Fetches a single refspec from the remote
+pub fn fetch_refspec(
+    repo_path: impl AsRef<Path>,
+    remote: &str,
+    refspec: &str,
+) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(repo_path)
+            .args(["fetch", remote, refspec]),
+    )?;
+    Ok(())
+}
+
+/// Fetches the history that a shallow or single-branch clone left out
+///
+/// With `all_branches` the remote's other branches are fetched too, and will be from then on.
+pub fn fetch_full_history(
+    repo_path: impl AsRef<Path>,
+    remote: &str,
+    shallow: bool,
+    all_branches: bool,
+) -> Result<(), WtError> {
+    let repo_path = repo_path.as_ref();
+    if all_branches {
+        run_git(git_command().current_dir(repo_path).args([
+            "remote",
+            "set-branches",
+            remote,
+            "*",
+        ]))?;
+    }
+    let mut cmd = git_command();
+    cmd.current_dir(repo_path).arg("fetch");
+    if shallow {
+        cmd.arg("--unshallow");
+    }
+    cmd.arg(remote);
+    run_git(&mut cmd)?;
+    Ok(())
+}
+
+/// Renames the branch checked out in the worktree, which keeps its upstream and reflog
+pub fn rename_branch(wt_path: impl AsRef<Path>, old: &str, new: &str) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(wt_path)
+            .args(["branch", "-m", old, new]),
+    )?;
+    Ok(())
+}
+
+/// Deletes a branch from the remote
+pub fn delete_remote_branch(
+    repo_path: impl AsRef<Path>,
+    remote: &str,
+    branch: &str,
+) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(repo_path)
+            .args(["push", remote, "--delete", branch]),
+    )?;
+    Ok(())
+}
+
+/// Fetches from all of the repository's remotes, deleting remote-tracking branches whose branch
+/// is gone from the remote
+#[instrument(skip_all, fields(path = traceable_path(repo_path.as_ref())))]
+pub fn fetch_all_and_prune(repo_path: impl AsRef<Path>) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(repo_path)
+            .args(["fetch", "--all", "--prune"]),
+    )?;
+    Ok(())
+}
+
+/// Returns the commit each remote-tracking branch points to, keyed by names like `origin/main`
+///
+/// Symbolic refs like `origin/HEAD` are left out since they only follow another branch.
+pub fn remote_tracking_branches(
+    repo_path: impl AsRef<Path>,
+) -> Result<BTreeMap<String, String>, WtError> {
+    let output = run_git(git_command().current_dir(repo_path).args([
+        "for-each-ref",
+        "--format=%(refname)%00%(objectname)%00%(symref)",
+        "refs/remotes",
+    ]))?;
+    let branches = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let name = fields.next()?.strip_prefix("refs/remotes/")?;
+            let sha = fields.next()?;
+            let symref = fields.next().unwrap_or_default();
+            symref
+                .is_empty()
+                .then(|| (name.to_string(), sha.to_string()))
+        })
+        .collect();
+    Ok(branches)
+}
+
+/// Fetches from all of the repository's remotes
+#[instrument(skip_all, fields(path = traceable_path(repo_path.as_ref())))]
+pub fn fetch_all(repo_path: impl AsRef<Path>) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(repo_path)
+            .args(["fetch", "--all"]),
+    )?;
+    Ok(())
+}
+
+/// Returns whether the worktree has uncommitted changes or untracked files
+pub fn is_dirty(wt_path: impl AsRef<Path>) -> Result<bool, WtError> {
+    let output = run_git(
+        git_command()
+            .current_dir(wt_path)
+            .args(["status", "--porcelain"]),
+    )?;
+    Ok(!output.stdout.is_empty())
+}
+
+/// Returns the upstream of the branch checked out in the worktree, if it has one
+pub fn upstream_branch(wt_path: impl AsRef<Path>) -> Result<Option<String>, WtError> {
+    let output = git_command()
+        .current_dir(wt_path)
+        .args([
+            "rev-parse",
+            "--abbrev-ref",
+            "--symbolic-full-name",
+            "@{upstream}",
+        ])
+        .output()
+        .context("call to git-rev-parse failed")?;
+    if !output.status.success() {
+        // git doesn't distinguish between "no upstream" and other failures here
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Returns how many commits the worktree's branch is (ahead, behind) its upstream
+pub fn ahead_behind(wt_path: impl AsRef<Path>) -> Result<(usize, usize), WtError> {
+    let output = run_git(git_command().current_dir(wt_path).args([
+        "rev-list",
+        "--left-right",
+        "--count",
+        "HEAD...@{upstream}",
+    ]))?;
+    let counts = String::from_utf8_lossy(&output.stdout);
+    let (ahead, behind) = counts
+        .trim()
+        .split_once('\t')
+        .context("unexpected output from git-rev-list")?;
+    let count = |n: &str| n.parse().context("unexpected output from git-rev-list");
+    Ok((count(ahead)?, count(behind)?))
+}
+
+/// Fast-forwards the worktree's branch to its upstream
+pub fn fast_forward(wt_path: impl AsRef<Path>) -> Result<(), WtError> {
+    run_git(
+        git_command()
+            .current_dir(wt_path)
+            .args(["merge", "--ff-only", "@{upstream}"]),
+    )?;
+    Ok(())
+}
+
+/// Information about a worktree in the project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorktreeInfo {
+    /// The name of the worktree
+    pub name: String,
+    /// The path of the worktree's working directory
+    pub path: PathBuf,
+    /// The branch checked out in the worktree, or `None` if HEAD is detached
+    pub branch: Option<String>,
+    /// Whether this is the main worktree
+    pub is_main: bool,
+    /// Whether the worktree's directory is missing, so git would prune it
+    pub prunable: bool,
+    /// Whether the worktree is locked
+    pub locked: bool,
+    /// Why the worktree was locked, if it's locked and a reason was given
+    pub lock_reason: Option<String>,
+    /// When `wt` created the worktree as an ISO 8601 timestamp, or `None` if it was created some
+    /// other way
+    pub created_at: Option<String>,
+}
+
+/// The commit a worktree's HEAD points to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeadCommit {
+    /// The abbreviated SHA of the commit
+    pub sha: String,
+    /// The first line of the commit's message
+    pub subject: String,
+}
+
+/// Returns the commit HEAD points to in the worktree, or `None` if HEAD is unborn
+pub fn head_commit(wt_path: impl AsRef<Path>) -> Result<Option<HeadCommit>, WtError> {
+    let wt = gix::open(wt_path.as_ref()).context("couldn't open worktree")?;
+    let Ok(commit) = wt.head_commit() else {
+        return Ok(None);
+    };
+    let message = commit.message().context("couldn't read HEAD commit")?;
+    Ok(Some(HeadCommit {
+        sha: commit.id.to_hex_with_len(7).to_string(),
+        subject: message.summary().to_string(),
+    }))
+}
+
+/// The file in a worktree's git directory recording when `wt` created the worktree
+const CREATED_AT_FILE: &str = "wt-created-at";
+
+/// Records the current time as the creation time of the worktree with the given git directory
+pub fn record_creation_time(wt_git_dir: impl AsRef<Path>) -> Result<(), WtError> {
+    let now = gix::date::Time::now_local_or_utc().format(gix::date::time::format::ISO8601_STRICT);
+    let path = wt_git_dir.as_ref().join(CREATED_AT_FILE);
+    std::fs::write(&path, format!("{now}\n"))
+        .with_context(|| format!("couldn't write {}", path.display()))?;
+    Ok(())
+}
+
+/// Returns when `wt` created the worktree with the given git directory, if it was recorded
+pub fn read_creation_time(wt_git_dir: impl AsRef<Path>) -> Option<gix::date::Time> {
+    let contents = std::fs::read_to_string(wt_git_dir.as_ref().join(CREATED_AT_FILE)).ok()?;
+    gix::date::parse(contents.trim(), None).ok()
+}
+
+/// Returns information about the worktrees other than the main worktree
+///
+/// Worktrees whose directories have been deleted are still included, marked as prunable.
+pub fn get_worktrees(repo: &Repository) -> Result<Vec<WorktreeInfo>, WtError> {
+    let worktrees = repo
+        .worktrees()
+        .context("couldn't get worktrees for repository")?;
+    worktrees
+        .into_iter()
+        .map(|wt| {
+            let name = wt.id().to_string();
+            let path = wt
+                .base()
+                .with_context(|| format!("couldn't get path of worktree '{name}'"))?;
+            let prunable = !path.exists();
+            let created_at = read_creation_time(wt.git_dir())
+                .map(|time| time.format(gix::date::time::format::ISO8601_STRICT));
+            let locked = wt.is_locked();
+            let lock_reason = wt
+                .lock_reason()
+                .map(|reason| reason.to_string())
+                .filter(|reason| !reason.is_empty());
+            let wt_repo = wt
+                .into_repo_with_possibly_inaccessible_worktree()
+                .with_context(|| format!("couldn't open worktree '{name}'"))?;
+            let branch = wt_repo
+                .head_name()
+                .with_context(|| format!("couldn't get branch of worktree '{name}'"))?
+                .map(|branch_ref| branch_from_ref(branch_ref.as_ref()))
+                .transpose()?;
+            Ok(WorktreeInfo {
+                name,
+                path,
+                branch,
+                is_main: false,
+                prunable,
+                locked,
+                lock_reason,
+                created_at,
+            })
+        })
+        .collect()
 }
 
-/// Returns a list of the worktrees other than the main worktree
-pub fn get_worktrees(repo: &Repository) -> Result<Vec<String>, Error> {
+/// Returns information about the main worktree, or `None` if the main repository is bare
+pub fn main_worktree_info(repo: &Repository) -> Result<Option<WorktreeInfo>, WtError> {
+    let Some(path) = repo.work_dir() else {
+        return Ok(None);
+    };
+    let name = path
+        .file_name()
+        .context("main worktree had no directory name")?
+        .to_string_lossy()
+        .to_string();
+    let branch = repo
+        .head_name()
+        .context("couldn't get branch of main worktree")?
+        .map(|branch_ref| branch_from_ref(branch_ref.as_ref()))
+        .transpose()?;
+    Ok(Some(WorktreeInfo {
+        name,
+        path: path.to_path_buf(),
+        branch,
+        is_main: true,
+        prunable: false,
+        locked: false,
+        lock_reason: None,
+        created_at: None,
+    }))
+}
+
+/// Names that stand for the worktree `wt` was invoked from, rather than a worktree's own name
+pub const CURRENT_WORKTREE_ALIASES: [&str; 2] = [".", "@"];
+
+/// Returns whether `name` is one of the [CURRENT_WORKTREE_ALIASES]
+pub fn is_current_worktree_alias(name: &str) -> bool {
+    CURRENT_WORKTREE_ALIASES.contains(&name)
+}
+
+/// Returns which of the project's `worktrees` contains the path `wt` was invoked from
+///
+/// The main worktree is only found if it's included in `worktrees`.
+pub fn current_worktree<'a>(
+    repos: &ProjectRepos,
+    worktrees: &'a [WorktreeInfo],
+) -> Result<&'a WorktreeInfo, WtError> {
+    let Some(current_path) = repos
+        .current_worktree_path()
+        .and_then(|p| p.canonicalize().ok())
+    else {
+        return Err(WtError::NotInAWorktree(
+            repos.current.git_dir().to_path_buf(),
+        ));
+    };
+    Ok(worktrees
+        .iter()
+        .find(|wt| wt.path.canonicalize().ok().as_ref() == Some(&current_path))
+        .with_context(|| {
+            format!(
+                "couldn't find the worktree at {} in the project",
+                current_path.display()
+            )
+        })?)
+}
+
+/// Returns the path of the worktree, including the main worktree, that has the branch checked out
+pub fn worktree_with_branch(repo: &Repository, branch: &str) -> Result<Option<PathBuf>, WtError> {
+    if let Some(main_path) = repo.work_dir() {
+        if current_branch_name(repo).ok().as_deref() == Some(branch) {
+            return Ok(Some(main_path.to_path_buf()));
+        }
+    }
+    Ok(get_worktrees(repo)?
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch))
+        .map(|wt| wt.path))
+}
+
+/// Returns the names of the worktrees other than the main worktree
+pub fn get_worktree_names(repo: &Repository) -> Result<Vec<String>, WtError> {
     let worktrees = repo
         .worktrees()
         .context("couldn't get worktrees for repository")?;
@@ -151,26 +1236,87 @@ pub fn get_worktrees(repo: &Repository) -> Result<Vec<String>, Error> {
         .collect::<Vec<_>>())
 }
 
+/// How much of a repository's history to clone
+#[derive(Debug, Clone, Default)]
+pub struct CloneOptions {
+    /// Only clone this many commits of history
+    pub depth: Option<u32>,
+    /// Only clone the history of the remote's default branch, which `depth` implies
+    pub single_branch: bool,
+    /// Clone a bare mirror of every ref in the remote
+    pub mirror: bool,
+    /// The name to give the remote instead of `origin`
+    pub origin: Option<String>,
+    /// The branch to check out instead of the one the remote's HEAD points to
+    pub branch: Option<String>,
+    /// Whether git may use the terminal to show progress and ask for credentials, otherwise it
+    /// fails instead of prompting
+    pub attached: bool,
+}
+
+impl CloneOptions {
+    /// Adds the arguments for these options to a `git clone` command
+    fn apply(&self, cmd: &mut Command) {
+        if let Some(depth) = self.depth {
+            cmd.arg(format!("--depth={depth}"));
+        }
+        if self.single_branch {
+            cmd.arg("--single-branch");
+        }
+        if self.mirror {
+            cmd.arg("--mirror");
+        }
+        if let Some(ref origin) = self.origin {
+            cmd.args(["--origin", origin]);
+        }
+        if let Some(ref branch) = self.branch {
+            cmd.args(["--branch", branch]);
+        }
+    }
+}
+
 /// Clones the provided repository into the specified directory with the specified name
 pub fn clone_repo(
     repo: impl AsRef<str>,
     clone_under: impl AsRef<Path>,
     name: Option<impl AsRef<str>>,
-) -> Result<PathBuf, Error> {
+) -> Result<PathBuf, WtError> {
+    clone_repo_with(repo, clone_under, name, &CloneOptions::default())
+}
+
+/// Clones the provided repository like [`clone_repo`], with only as much history as `opts` asks
+/// for
+pub fn clone_repo_with(
+    repo: impl AsRef<str>,
+    clone_under: impl AsRef<Path>,
+    name: Option<impl AsRef<str>>,
+    opts: &CloneOptions,
+) -> Result<PathBuf, WtError> {
     let clone_under = clone_under.as_ref();
     let repo = repo.as_ref();
     std::fs::create_dir_all(clone_under).context("couldn't create clone directory")?;
     let directories_before_clone = directories_immediately_under_path(clone_under)
         .context("couldn't get child directories before clone")?;
-    let mut cmd = Command::new("git");
+    let mut cmd = git_command();
     cmd.current_dir(clone_under);
-    cmd.args(["clone", repo]);
+    cmd.arg("clone");
+    opts.apply(&mut cmd);
+    cmd.arg(repo);
     if let Some(name) = name {
         cmd.arg(name.as_ref());
     }
-    let output = cmd.output()?;
-    if !output.status.success() {
-        bail!("{}", String::from_utf8_lossy(&output.stderr));
+    let cloned = if opts.attached {
+        run_git_attached(&mut cmd)
+    } else {
+        // Nobody would see the prompt, so it would look like the clone was hanging
+        cmd.env("GIT_TERMINAL_PROMPT", "0");
+        run_git(&mut cmd).map(|_| ())
+    };
+    if let Err(err) = cloned {
+        if is_auth_failure(&err) {
+            return Err(err.context(credentials_hint(repo)));
+        }
+        return Err(err);
     }
     let directories_after_clone = directories_immediately_under_path(clone_under)
         .context("couldn't get child directories after clone")?;
@@ -179,16 +1325,70 @@ pub fn clone_repo(
         .cloned()
         .collect::<Vec<_>>();
     if dir_diff.len() > 1 {
-        bail!("clone created more than one directory: {:?}", dir_diff);
+        return Err(anyhow!("clone created more than one directory: {:?}", dir_diff).into());
     }
     if dir_diff.is_empty() {
-        bail!("clone didn't create a directory");
+        return Err(anyhow!("clone didn't create a directory").into());
     }
     Ok(dir_diff[0].clone())
 }
 
+/// Explains what to do when the remote wants credentials that git doesn't have
+pub fn credentials_hint(url: &str) -> String {
+    format!(
+        "couldn't authenticate with {url}, check that you have access and that git has \
+         credentials for it, e.g. from a credential helper or GIT_ASKPASS (see `wt clone --help`)"
+    )
+}
+
+/// Checks that a remote repository can be reached, giving up after `timeout`
+///
+/// git isn't allowed to ask for credentials, so a remote that needs ones git doesn't already have
+/// fails with an error that [is_auth_failure] recognizes.
+pub fn check_remote_reachable(url: &str, timeout: Duration) -> Result<(), WtError> {
+    let mut child = git_command()
+        .args(["ls-remote", "--heads", url])
+        // Prompting for credentials would hang until the timeout
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("call to git-ls-remote failed")?;
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .context("couldn't wait for git-ls-remote")?
+        {
+            if status.success() {
+                return Ok(());
+            }
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                pipe.read_to_string(&mut stderr).ok();
+            }
+            let failure = GitFailure {
+                args: ["git", "ls-remote", "--heads", url]
+                    .map(String::from)
+                    .to_vec(),
+                code: status.code(),
+                stdout: String::new(),
+                stderr: stderr.trim().to_string(),
+            };
+            return Err(WtError::GitCommandFailed(failure));
+        }
+        if start.elapsed() > timeout {
+            child.kill().ok();
+            child.wait().ok();
+            return Err(WtError::RemoteTimedOut(timeout));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
 /// Returns a set of directories immediately under the provided path
-fn directories_immediately_under_path(p: impl AsRef<Path>) -> Result<HashSet<PathBuf>, Error> {
+fn directories_immediately_under_path(p: impl AsRef<Path>) -> Result<HashSet<PathBuf>, WtError> {
     let path = p.as_ref();
     let dirs = path
         .read_dir()
@@ -208,21 +1408,26 @@ fn directories_immediately_under_path(p: impl AsRef<Path>) -> Result<HashSet<Pat
 }
 
 /// Extracts the branch name from a full reference name
-pub fn branch_from_ref(ref_name: &FullNameRef) -> Result<String, Error> {
+pub fn branch_from_ref(ref_name: &FullNameRef) -> Result<String, WtError> {
     Ok(ref_name
         .as_bstr()
         .to_string()
-        .split('/')
-        .nth(2) // skip the "refs/heads/" prefix
+        .strip_prefix("refs/heads/")
         .context("failed to get branch name from ref")?
         .to_string())
 }
 
+/// Returns the full reference name of a branch
+pub fn ref_from_branch(branch: impl AsRef<str>) -> Result<FullName, WtError> {
+    let branch = branch.as_ref();
+    Ok(FullName::try_from(format!("refs/heads/{branch}"))
+        .with_context(|| format!("invalid branch name '{branch}'"))?)
+}
+
 /// Returns the name of the branch currently checked out in the repo
-#[allow(dead_code)]
-pub fn current_branch_name(repo: &Repository) -> Result<String, Error> {
+pub fn current_branch_name(repo: &Repository) -> Result<String, WtError> {
     let branch_ref = get_worktree_branch_ref(repo).context("couldn't get ref of current branch")?;
-    branch_from_ref(branch_ref.as_ref()).context("couldn't get branch name from ref")
+    Ok(branch_from_ref(branch_ref.as_ref()).context("couldn't get branch name from ref")?)
 }
 
 #[cfg(test)]
@@ -252,15 +1457,80 @@ mod test {
         assert_eq!(branch_name, current_branch);
     }
 
+    #[test]
+    fn parses_git_versions() {
+        assert_eq!(parse_git_version("git version 2.39.5\n"), Some((2, 39)));
+        assert_eq!(
+            parse_git_version("git version 2.39.3 (Apple Git-145)"),
+            Some((2, 39))
+        );
+        assert_eq!(
+            parse_git_version("git version 2.41.0.windows.1"),
+            Some((2, 41))
+        );
+        assert_eq!(parse_git_version("not git"), None);
+        assert!(check_git().is_ok());
+    }
+
+    #[test]
+    fn branch_names_keep_slashes() {
+        let branch_ref = ref_from_branch("feature/login").unwrap();
+        assert_eq!(
+            branch_from_ref(branch_ref.as_ref()).unwrap(),
+            "feature/login"
+        );
+    }
+
+    #[test]
+    fn validates_branch_names() {
+        assert!(validate_branch_name("trunk").is_ok());
+        assert!(validate_branch_name("feature/login").is_ok());
+        assert!(matches!(
+            validate_branch_name("has space"),
+            Err(WtError::InvalidBranchName(name, _)) if name == "has space"
+        ));
+        assert!(validate_branch_name("double..dot").is_err());
+        assert!(validate_branch_name("ends.lock").is_err());
+    }
+
+    #[test]
+    fn explains_invalid_branch_names() {
+        let problem = |name| branch_name_problem(name).unwrap();
+        assert_eq!(problem("has space"), "' ' at position 4 isn't allowed");
+        assert_eq!(problem("fix~1"), "'~' at position 4 isn't allowed");
+        assert_eq!(problem("a..b"), "'..' at position 2 isn't allowed");
+        assert_eq!(problem("x@{1}"), "'@{' at position 2 isn't allowed");
+        assert_eq!(problem("feature/x.lock"), "'x.lock' can't end with '.lock'");
+        assert_eq!(problem("feature/.hidden"), "'.hidden' can't start with '.'");
+        assert_eq!(problem("-b"), "branch names can't start with '-'");
+        assert!(branch_name_problem("feature/login-2").is_none());
+    }
+
+    #[test]
+    fn prefers_repo_local_default_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        gix::init(temp_dir.path()).unwrap();
+        let output = Command::new("git")
+            .current_dir(temp_dir.path())
+            .args(["config", "--local", "init.defaultBranch", "local-default"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let repo = gix::open(temp_dir.path()).unwrap();
+        assert_eq!(default_branch_name(Some(&repo)).unwrap(), "local-default");
+        assert_ne!(default_branch_name(None).unwrap(), "local-default");
+    }
+
     #[test]
     fn reads_worktrees() {
         let temp_dir = tempfile::tempdir().unwrap();
         let init_opts = Init {
             name: "test_proj".to_string(),
             path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
         };
         let main_wt_path = init(&init_opts).unwrap();
-        let default_branch = global_default_branch_name().unwrap();
+        let default_branch = default_branch_name(None).unwrap();
         let repo = gix::open(temp_dir.path().join("test_proj").join(&default_branch)).unwrap();
 
         // Create the worktree branch before creating the worktree
@@ -279,7 +1549,63 @@ mod test {
         .unwrap();
         let worktrees = get_worktrees(&repo).unwrap();
         assert_eq!(worktrees.len(), 1);
-        assert_eq!(worktrees[0], "new_worktree".to_string());
+        assert_eq!(
+            worktrees[0],
+            WorktreeInfo {
+                name: "new_worktree".to_string(),
+                path: temp_dir.path().join("test_proj").join("new_worktree"),
+                branch: Some("new_worktree_branch".to_string()),
+                is_main: false,
+                prunable: false,
+                locked: false,
+                lock_reason: None,
+                created_at: None,
+            }
+        );
+        assert_eq!(
+            get_worktree_names(&repo).unwrap(),
+            vec!["new_worktree".to_string()]
+        );
+    }
+
+    #[test]
+    fn worktree_config_keeps_bare_repo_bare() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".to_string(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".to_string()),
+            bare: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let git_dir = temp_dir.path().join("test_proj").join(".git");
+        // Enabling it again shouldn't fail now that `core.bare` has moved
+        enable_worktree_config(&git_dir).unwrap();
+        enable_worktree_config(&git_dir).unwrap();
+        assert!(!is_dirty(&main_wt_path).unwrap());
+        assert!(get_main_worktree(&main_wt_path).unwrap().is_bare());
+    }
+
+    #[test]
+    fn reads_worktrees_of_bare_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".to_string(),
+            path: Some(temp_dir.path().to_path_buf()),
+            branch: Some("trunk".to_string()),
+            bare: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let main_repo = get_main_worktree(&main_wt_path).unwrap();
+        assert!(main_repo.is_bare());
+        assert_eq!(
+            project_worktree_path(&main_repo, "other", None).unwrap(),
+            temp_dir.path().join("test_proj").join("other")
+        );
+        let worktrees = get_worktree_names(&main_repo).unwrap();
+        assert_eq!(worktrees, vec!["trunk".to_string()]);
     }
 
     #[test]
@@ -320,4 +1646,66 @@ mod test {
 
         assert!(clone_dir.join("new_name").join(".git").exists());
     }
+
+    #[test]
+    fn discovers_project_repos_once() {
+        let temp_dir = tempdir().unwrap();
+        let main_wt_path = init(&Init {
+            name: "test_proj".to_string(),
+            path: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let wt_path = temp_dir.path().join("test_proj").join("other");
+        create_branch(&main_wt_path, "other").unwrap();
+        new_worktree(&main_wt_path, &wt_path, "other").unwrap();
+        let nested = wt_path.join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        let repos = ProjectRepos::discover(&nested).unwrap();
+        assert_eq!(repos.current_worktree_path(), Some(wt_path.as_path()));
+        assert_eq!(repos.main.work_dir(), Some(main_wt_path.as_path()));
+        let repos = ProjectRepos::discover(&main_wt_path).unwrap();
+        assert_eq!(repos.current.git_dir(), repos.main.git_dir());
+    }
+
+    #[test]
+    fn parses_identities() {
+        assert_eq!(
+            Identity::from_str(" Jane Doe <jane@example.com> ").unwrap(),
+            Identity {
+                name: "Jane Doe".into(),
+                email: "jane@example.com".into(),
+            }
+        );
+        for invalid in ["Jane Doe", "<jane@example.com>", "Jane <>", "Jane <a> <b>"] {
+            assert!(Identity::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn requires_signing_key_for_ssh_signing() {
+        let temp_dir = tempdir().unwrap();
+        gix::init(temp_dir.path()).unwrap();
+        let git_config = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(temp_dir.path())
+                .arg("config")
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git_config(&["gpg.format", "ssh"]);
+        // An empty key hides any signing key from the global config
+        git_config(&["user.signingkey", ""]);
+        git_config(&["commit.gpgsign", "true"]);
+        let repo = gix::open(temp_dir.path()).unwrap();
+        assert!(signs_commits(Some(&repo)).unwrap());
+        let err = check_signing_configured(Some(&repo)).unwrap_err();
+        assert!(err.to_string().contains("user.signingKey"));
+        git_config(&["user.signingkey", "~/.ssh/id_ed25519.pub"]);
+        let repo = gix::open(temp_dir.path()).unwrap();
+        assert!(check_signing_configured(Some(&repo)).is_ok());
+    }
 }
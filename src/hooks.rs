@@ -0,0 +1,58 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{bail, Context};
+use tracing::{debug, instrument};
+
+use crate::{git::WorktreeInfo, util::traceable_path, Error};
+
+/// Runs a hook command with `sh` in the worktree's directory
+///
+/// The hook can find out which worktree it's running for through the `WT_NAME`, `WT_PATH`, and
+/// `WT_BRANCH` environment variables. `WT_BRANCH` is empty when HEAD is detached.
+#[instrument(skip(info), fields(wt = info.name, path = traceable_path(&info.path)))]
+pub fn run_hook(hook: &str, cmd: &str, info: &WorktreeInfo) -> Result<(), Error> {
+    debug!(cmd, "running hook");
+    let status = hook_command(cmd, &info.path, info)
+        .status()
+        .with_context(|| format!("couldn't run {hook} hook"))?;
+    if !status.success() {
+        bail!("{hook} hook failed with {status}: {cmd}");
+    }
+    Ok(())
+}
+
+/// Builds the command that runs a hook in `dir`
+fn hook_command(cmd: &str, dir: &Path, info: &WorktreeInfo) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .args(["-c", cmd])
+        .current_dir(dir)
+        .env("WT_NAME", &info.name)
+        .env("WT_PATH", &info.path)
+        .env("WT_BRANCH", info.branch.as_deref().unwrap_or_default());
+    command
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn runs_hook_with_worktree_env() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let info = WorktreeInfo {
+            name: "feature".into(),
+            path: temp_dir.path().to_path_buf(),
+            branch: Some("alice/feature".into()),
+            is_main: false,
+            prunable: false,
+            locked: false,
+            lock_reason: None,
+            created_at: None,
+        };
+        run_hook("test", "echo \"$WT_NAME $WT_BRANCH\" > out.txt", &info).unwrap();
+        let out = std::fs::read_to_string(temp_dir.path().join("out.txt")).unwrap();
+        assert_eq!(out, "feature alice/feature\n");
+        assert!(run_hook("test", "exit 3", &info).is_err());
+    }
+}
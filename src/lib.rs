@@ -0,0 +1,16 @@
+//! Utilities for managing projects whose branches are checked out in sibling git worktrees
+
+pub mod commands;
+pub mod config;
+pub mod error;
+pub mod git;
+pub mod hooks;
+pub mod pager;
+pub mod project_lock;
+pub mod prompt;
+pub mod tmux;
+pub mod util;
+
+pub use commands::{init, init_via_clone, list, new, remove, root, which};
+
+pub type Error = anyhow::Error;
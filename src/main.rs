@@ -1,20 +1,35 @@
+use std::process::ExitCode;
+
 use clap::{CommandFactory, Parser};
-use commands::run;
+use cli::run;
 use tracing::debug;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
 use tracing_tree::HierarchicalLayer;
+use wt::{
+    error::exit_code,
+    git::check_git,
+    util::{disable_color, paint, Stream, ERROR_STYLE},
+    Error,
+};
 
-use crate::commands::Cli;
+use crate::cli::{Cli, Commands};
 
-mod commands;
-mod git;
-mod util;
+mod cli;
 
-type Error = anyhow::Error;
+fn main() -> ExitCode {
+    match try_main() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{} {err:?}", paint("Error:", ERROR_STYLE, Stream::Stderr));
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
 
-fn main() -> Result<(), Error> {
+fn try_main() -> Result<(), Error> {
+    let args = Cli::parse();
     Registry::default()
-        .with(EnvFilter::from_default_env())
+        .with(env_filter(args.global_opts.verbose))
         .with(
             HierarchicalLayer::new(2)
                 .with_targets(true)
@@ -22,9 +37,15 @@ fn main() -> Result<(), Error> {
         )
         .init();
     debug!("starting up");
-    let args = Cli::parse();
+    if args.global_opts.no_color {
+        disable_color();
+    }
     match args.command {
         Some(cmd) => {
+            // The doctor reports a missing git itself
+            if !matches!(cmd, Commands::Doctor) {
+                check_git()?;
+            }
             run(&cmd, &args.global_opts)?;
             Ok(())
         }
@@ -36,3 +57,15 @@ fn main() -> Result<(), Error> {
         }
     }
 }
+
+/// Returns the filter for tracing output, which `RUST_LOG` takes precedence over
+fn env_filter(verbosity: u8) -> EnvFilter {
+    if std::env::var_os(EnvFilter::DEFAULT_ENV).is_some() {
+        return EnvFilter::from_default_env();
+    }
+    match verbosity {
+        0 => EnvFilter::default(),
+        1 => EnvFilter::new("wt=debug"),
+        _ => EnvFilter::new("wt=trace"),
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    io::{IsTerminal, Write},
+    process::{Command, ExitStatus, Stdio},
+};
+
+use anyhow::Context;
+
+use crate::Error;
+
+/// The pager to use when `PAGER` isn't set
+const DEFAULT_PAGER: &str = "less";
+
+/// The options given to `less` when `LESS` isn't set, the same ones git uses: quit if the output
+/// fits on one screen, pass colors through, and don't clear the screen on exit
+const DEFAULT_LESS: &str = "FRX";
+
+/// Prints `output` to stdout, piping it through a pager if `enabled` and it doesn't fit in the
+/// terminal
+///
+/// Output is never paged unless stdout is a terminal, so scripts always see it directly.
+pub fn print_paged(output: &str, enabled: bool) -> Result<(), Error> {
+    let pager = pager_command(std::env::var("PAGER").ok());
+    let fits = terminal_height().is_some_and(|height| output.lines().count() < height);
+    match pager {
+        Some(pager) if enabled && !fits && std::io::stdout().is_terminal() => page(&pager, output),
+        _ => {
+            print!("{output}");
+            Ok(())
+        }
+    }
+}
+
+/// Returns the pager to run with `sh`, or `None` if paging has been turned off by setting
+/// `PAGER` to an empty string or `cat`
+fn pager_command(pager_var: Option<String>) -> Option<String> {
+    match pager_var {
+        None => Some(DEFAULT_PAGER.to_string()),
+        Some(pager) if pager.trim().is_empty() || pager.trim() == "cat" => None,
+        Some(pager) => Some(pager),
+    }
+}
+
+/// Returns the height of the terminal stdout is connected to
+///
+/// `LINES` overrides the size the terminal reports, like it does for curses programs.
+fn terminal_height() -> Option<usize> {
+    if let Some(lines) = std::env::var("LINES")
+        .ok()
+        .and_then(|lines| lines.trim().parse().ok())
+    {
+        return Some(lines);
+    }
+    let (_, terminal_size::Height(height)) = terminal_size::terminal_size()?;
+    Some(height.into())
+}
+
+/// Returns whether `sh` exited because it couldn't find or run the pager, rather than the pager
+/// itself exiting
+fn failed_to_start(status: ExitStatus) -> bool {
+    matches!(status.code(), Some(126 | 127))
+}
+
+/// Writes `output` to the pager, falling back to printing it if the pager can't be started
+fn page(pager: &str, output: &str) -> Result<(), Error> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", pager]).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", DEFAULT_LESS);
+    }
+    let Ok(mut child) = cmd.spawn() else {
+        print!("{output}");
+        return Ok(());
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closing early, e.g. quitting `less` before the end, isn't an error
+        stdin.write_all(output.as_bytes()).ok();
+    }
+    let status = child.wait().context("couldn't wait for pager")?;
+    if failed_to_start(status) {
+        // `sh` already explained what was wrong with the pager
+        print!("{output}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn picks_pager() {
+        assert_eq!(pager_command(None).as_deref(), Some("less"));
+        assert_eq!(
+            pager_command(Some("most -s".into())).as_deref(),
+            Some("most -s")
+        );
+        assert_eq!(pager_command(Some("".into())), None);
+        assert_eq!(pager_command(Some("cat".into())), None);
+    }
+
+    #[test]
+    fn notices_missing_pager() {
+        let status = |pager: &str| Command::new("sh").args(["-c", pager]).status().unwrap();
+        assert!(failed_to_start(status("wt-no-such-pager 2>/dev/null")));
+        assert!(!failed_to_start(status("true")));
+        assert!(!failed_to_start(status("exit 1")));
+    }
+}
@@ -0,0 +1,93 @@
+//! An advisory lock that keeps concurrent `wt` invocations from changing a project's worktrees at
+//! the same time
+
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use tracing::debug;
+
+use crate::{error::WtError, util::traceable_path, Error};
+
+/// The name of the lock file, which lives in the project root
+pub const LOCK_FILE_NAME: &str = ".wt.lock";
+
+/// How long to wait for another `wt` to finish before giving up
+const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long to sleep between attempts to take the lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Holds the project's lock file until dropped
+#[derive(Debug)]
+pub struct ProjectLock {
+    path: PathBuf,
+}
+
+impl ProjectLock {
+    /// Takes the lock for the project rooted at `project_root`, waiting briefly if another `wt`
+    /// holds it
+    pub fn acquire(project_root: &Path) -> Result<Self, Error> {
+        Self::acquire_with_timeout(project_root, LOCK_TIMEOUT)
+    }
+
+    fn acquire_with_timeout(project_root: &Path, timeout: Duration) -> Result<Self, Error> {
+        let path = project_root.join(LOCK_FILE_NAME);
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // The PID is only there to help whoever finds a lock left behind by a crash
+                    let _ = writeln!(file, "{}", std::process::id());
+                    debug!(path = traceable_path(&path), "took project lock");
+                    return Ok(Self { path });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if start.elapsed() >= timeout {
+                        return Err(WtError::Busy(path).into());
+                    }
+                    std::thread::sleep(RETRY_INTERVAL);
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("couldn't create lock file: {}", path.display()))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            debug!(
+                path = traceable_path(&self.path),
+                error = err.to_string(),
+                "couldn't remove project lock"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn excludes_other_holders_until_dropped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lock = ProjectLock::acquire(temp_dir.path()).unwrap();
+        let err = ProjectLock::acquire_with_timeout(temp_dir.path(), Duration::ZERO).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(WtError::Busy(_))));
+        assert!(err
+            .to_string()
+            .contains("another wt operation is in progress"));
+        drop(lock);
+        assert!(!temp_dir.path().join(LOCK_FILE_NAME).exists());
+        assert!(ProjectLock::acquire(temp_dir.path()).is_ok());
+    }
+}
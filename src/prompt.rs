@@ -0,0 +1,58 @@
+use std::io::IsTerminal;
+
+use anyhow::{bail, Context};
+
+use inquire::InquireError;
+
+use crate::{error::WtError, Error};
+
+/// Returns whether the user can be prompted for input
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// Returns whether [confirm] can get an answer, either by asking or because of `assume_yes`
+pub fn can_confirm(assume_yes: bool) -> bool {
+    assume_yes || is_interactive()
+}
+
+/// Asks the user a yes/no question, defaulting to "no"
+///
+/// This returns `true` without prompting if `assume_yes` is set, e.g. because of a `--yes` flag,
+/// and fails with a message explaining how to skip the prompt if the user can't be prompted.
+pub fn confirm(msg: &str, assume_yes: bool) -> Result<bool, Error> {
+    if assume_yes {
+        return Ok(true);
+    }
+    if !is_interactive() {
+        bail!("can't ask for confirmation because stdin isn't a terminal, pass --yes to confirm");
+    }
+    cancellable(
+        inquire::Confirm::new(msg).with_default(false).prompt(),
+        "failed to get confirmation",
+    )
+}
+
+/// Asks the user to pick one of the options
+pub fn select(msg: &str, options: Vec<String>) -> Result<String, Error> {
+    if !is_interactive() {
+        bail!(
+            "can't ask which of {} was meant because stdin isn't a terminal",
+            options.join(", ")
+        );
+    }
+    cancellable(
+        inquire::Select::new(msg, options).prompt(),
+        "failed to get selection",
+    )
+}
+
+/// Turns the user escaping out of a prompt into a [WtError::Cancelled]
+pub fn cancellable<T>(res: Result<T, InquireError>, context: &'static str) -> Result<T, Error> {
+    match res {
+        Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+            Err(WtError::Cancelled("cancelled by user".to_string()).into())
+        }
+        res => res.context(context),
+    }
+}
@@ -0,0 +1,49 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{bail, Context};
+
+use crate::Error;
+
+/// Returns whether `wt` is running inside a tmux session
+pub fn in_tmux() -> bool {
+    std::env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+}
+
+/// Returns the arguments to `tmux` that open a window named `name` in `dir`
+pub fn new_window_args(name: &str, dir: &Path) -> Vec<String> {
+    vec![
+        "new-window".to_string(),
+        "-n".to_string(),
+        name.to_string(),
+        "-c".to_string(),
+        dir.display().to_string(),
+    ]
+}
+
+/// Opens a new tmux window named `name` whose working directory is `dir`
+pub fn open_window(name: &str, dir: &Path) -> Result<(), Error> {
+    if !in_tmux() {
+        bail!("not inside a tmux session, start tmux first or leave off --tmux");
+    }
+    let output = Command::new("tmux")
+        .args(new_window_args(name, dir))
+        .output()
+        .context("call to tmux failed, is it installed?")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builds_new_window_args() {
+        assert_eq!(
+            new_window_args("foo", Path::new("/proj/foo")),
+            vec!["new-window", "-n", "foo", "-c", "/proj/foo"]
+        );
+    }
+}
@@ -1,7 +1,159 @@
-use std::path::Path;
+use std::{
+    fmt::Display,
+    io::IsTerminal,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anstyle::{AnsiColor, Style};
+use anyhow::Context;
+
+/// Style for the worktree `wt` was called from
+pub const CURRENT_STYLE: Style = AnsiColor::Green.on_default().bold();
+/// Style for worktrees with uncommitted changes
+pub const DIRTY_STYLE: Style = AnsiColor::Red.on_default();
+/// Style for locked worktrees
+pub const LOCKED_STYLE: Style = AnsiColor::Yellow.on_default();
+/// Style for error messages
+pub const ERROR_STYLE: Style = AnsiColor::Red.on_default().bold();
+/// Style for text that should stand out
+pub const EMPHASIS_STYLE: Style = Style::new().bold();
+
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// The stream that styled text will be written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
 
 /// Returns a `tracing`-compatible form of a [Path]
 pub fn traceable_path(p: impl AsRef<Path>) -> impl tracing::Value {
     let path = p.as_ref();
     path.display().to_string()
 }
+
+/// Disables colored output for the rest of the program, e.g. because of a `--no-color` flag
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether output written to the stream should be colored
+///
+/// Color is disabled when `NO_COLOR` is set, when it has been disabled via [disable_color], or
+/// when the stream isn't a terminal.
+pub fn color_enabled(stream: Stream) -> bool {
+    if COLOR_DISABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    match stream {
+        Stream::Stdout => std::io::stdout().is_terminal(),
+        Stream::Stderr => std::io::stderr().is_terminal(),
+    }
+}
+
+/// Applies the style to the text if color is enabled for the stream
+pub fn paint(text: impl Display, style: Style, stream: Stream) -> String {
+    if color_enabled(stream) {
+        format!("{style}{text}{style:#}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Returns whether the path exists and has any contents
+pub fn dir_is_occupied(path: &Path) -> Result<bool, crate::Error> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let mut entries = path
+        .read_dir()
+        .with_context(|| format!("couldn't read directory: {}", path.display()))?;
+    Ok(entries.next().is_some())
+}
+
+/// The minimum similarity for a name to be considered a match for a mistyped name
+const MATCH_THRESHOLD: f64 = 0.7;
+/// How much less similar than the best match a name can be and still be considered ambiguous
+const MATCH_MARGIN: f64 = 0.1;
+
+/// Returns the candidates similar enough to `name` to be what the user meant, best match first
+///
+/// An empty list means nothing is close, and a single entry is a clear match. Candidates that are
+/// much less similar than the best match are dropped.
+pub fn closest_matches(name: &str, candidates: &[String]) -> Vec<String> {
+    let mut scored = candidates
+        .iter()
+        .map(|c| (strsim::normalized_damerau_levenshtein(name, c), c))
+        .filter(|(score, _)| *score >= MATCH_THRESHOLD)
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let Some(&(best, _)) = scored.first() else {
+        return Vec::new();
+    };
+    scored
+        .into_iter()
+        .filter(|(score, _)| best - score <= MATCH_MARGIN)
+        .map(|(_, c)| c.clone())
+        .collect()
+}
+
+/// Returns the number of seconds since the Unix epoch of a timestamp like those recorded by
+/// [crate::git::record_creation_time]
+pub fn timestamp_seconds(timestamp: &str) -> Option<i64> {
+    gix::date::parse(timestamp, None)
+        .ok()
+        .map(|time| time.seconds)
+}
+
+/// Describes how long before `now` a timestamp was, e.g. `3 days ago`
+pub fn format_age(timestamp: &str, now: SystemTime) -> Option<String> {
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    let age = (now - timestamp_seconds(timestamp)?).max(0);
+    let (count, unit) = match age {
+        0..=59 => return Some("just now".to_string()),
+        60..=3599 => (age / 60, "minute"),
+        3600..=86399 => (age / 3600, "hour"),
+        _ => (age / 86400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    Some(format!("{count} {unit}{plural} ago"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_closest_matches() {
+        let candidates = ["feat-login", "feat-logout", "fix-1", "fix-2", "docs"].map(String::from);
+        assert_eq!(
+            closest_matches("feat-logn", &candidates),
+            vec!["feat-login"]
+        );
+        assert_eq!(
+            closest_matches("fix-3", &candidates),
+            vec!["fix-1", "fix-2"]
+        );
+        assert_eq!(closest_matches("dosc", &candidates), vec!["docs"]);
+        assert!(closest_matches("release", &candidates).is_empty());
+    }
+
+    #[test]
+    fn formats_ages() {
+        let timestamp = "2024-05-01T12:00:00+00:00";
+        let created = UNIX_EPOCH
+            + std::time::Duration::from_secs(timestamp_seconds(timestamp).unwrap() as u64);
+        let age = |secs| format_age(timestamp, created + std::time::Duration::from_secs(secs));
+        assert_eq!(age(30).as_deref(), Some("just now"));
+        assert_eq!(age(60).as_deref(), Some("1 minute ago"));
+        assert_eq!(age(2 * 3600 + 5).as_deref(), Some("2 hours ago"));
+        assert_eq!(age(3 * 86400).as_deref(), Some("3 days ago"));
+        assert_eq!(format_age("not a time", created), None);
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs `wt doctor` from the specified directory with git's and wt's configuration files out of
+/// the way, so only `WT_DEFAULT_BRANCH` can pick the default branch
+fn doctor(dir: &Path, home: &Path, default_branch: Option<&str>) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_wt"));
+    cmd.current_dir(dir)
+        .arg("doctor")
+        .env("HOME", home)
+        .env_remove("XDG_CONFIG_HOME")
+        .env("GIT_CONFIG_GLOBAL", home.join("gitconfig"))
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env_remove("WT_DEFAULT_BRANCH");
+    if let Some(branch) = default_branch {
+        cmd.env("WT_DEFAULT_BRANCH", branch);
+    }
+    cmd.output().unwrap()
+}
+
+#[test]
+fn reports_where_default_branch_comes_from() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let output = doctor(temp_dir.path(), temp_dir.path(), Some("trunk"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("new projects will use 'trunk', from $WT_DEFAULT_BRANCH"),
+        "{stdout}"
+    );
+
+    let output = doctor(temp_dir.path(), temp_dir.path(), None);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("new projects will use 'main', from wt's built-in default"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,154 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs `wt` from the specified directory with extra environment variables, which git should
+/// see as if it had been run by hand
+fn wt_with_env(dir: &Path, args: &[&str], env: &[(&str, &Path)]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_wt"));
+    cmd.current_dir(dir)
+        .args(args)
+        .env_remove("WT_PROJECTS_DIR")
+        .env_remove("RUST_BACKTRACE");
+    // Identities from the environment would hide whether git read them from its config
+    for var in [
+        "GIT_AUTHOR_NAME",
+        "GIT_AUTHOR_EMAIL",
+        "GIT_COMMITTER_NAME",
+        "GIT_COMMITTER_EMAIL",
+        "EMAIL",
+    ] {
+        cmd.env_remove(var);
+    }
+    for (var, value) in env {
+        cmd.env(var, value);
+    }
+    cmd.output().unwrap()
+}
+
+/// Writes an executable shell script
+fn write_script(path: &Path, contents: &str) {
+    std::fs::write(path, contents).unwrap();
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Creates a repository with a single commit to clone
+fn source_repo(path: &Path) {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .current_dir(path)
+            .args([
+                "-c",
+                "user.name=Source",
+                "-c",
+                "user.email=source@example.com",
+            ])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+    };
+    std::fs::create_dir_all(path).unwrap();
+    git(&["init", "-q"]);
+    git(&["commit", "-q", "--allow-empty", "-m", "initial commit"]);
+}
+
+#[test]
+fn clone_uses_git_ssh_command() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let repo = temp_dir.path().join("repo");
+    source_repo(&repo);
+    // Stands in for ssh by running the requested git command locally, recording the host it was
+    // asked to connect to
+    let log = temp_dir.path().join("ssh.log");
+    let fake_ssh = temp_dir.path().join("fake-ssh");
+    write_script(
+        &fake_ssh,
+        &format!(
+            "#!/bin/sh\nfor arg; do last=\"$arg\"; done\necho \"$1\" >> '{}'\nexec sh -c \"$last\"\n",
+            log.display()
+        ),
+    );
+
+    let url = format!("ssh://fakehost{}", repo.display());
+    let output = wt_with_env(
+        temp_dir.path(),
+        &["clone", &url, "--name", "proj"],
+        &[("GIT_SSH_COMMAND", &fake_ssh)],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(std::fs::read_to_string(&log).unwrap().contains("fakehost"));
+}
+
+#[test]
+fn worktree_operations_use_git_config_global() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let home = temp_dir.path().join("home");
+    std::fs::create_dir(&home).unwrap();
+    // A hook configured only in the global config file shows whether git read it
+    let hooks = temp_dir.path().join("hooks");
+    std::fs::create_dir(&hooks).unwrap();
+    let marker = temp_dir.path().join("checked-out");
+    write_script(
+        &hooks.join("post-checkout"),
+        &format!("#!/bin/sh\ntouch '{}'\n", marker.display()),
+    );
+    let global_config = temp_dir.path().join("gitconfig");
+    std::fs::write(
+        &global_config,
+        format!(
+            "[user]\n\tname = Global User\n\temail = global@example.com\n[core]\n\thooksPath = {}\n",
+            hooks.display()
+        ),
+    )
+    .unwrap();
+    let env = [
+        ("HOME", home.as_path()),
+        ("GIT_CONFIG_GLOBAL", global_config.as_path()),
+        ("GIT_CONFIG_NOSYSTEM", Path::new("1")),
+    ];
+
+    let output = wt_with_env(temp_dir.path(), &["init", "proj"], &env);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let main_wt = String::from_utf8(output.stdout).unwrap();
+    let author = Command::new("git")
+        .arg("-C")
+        .arg(main_wt.trim())
+        .args(["log", "-1", "--format=%an"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&author.stdout).trim(),
+        "Global User"
+    );
+
+    let output = wt_with_env(Path::new(main_wt.trim()), &["new", "feature"], &env);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(marker.exists());
+}
+
+#[test]
+fn clone_uses_proxy_settings() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    // Nothing listens on port 1, so the clone fails trying to reach the proxy rather than the host
+    let output = wt_with_env(
+        temp_dir.path(),
+        &["clone", "http://example.invalid/repo.git"],
+        &[("http_proxy", Path::new("http://127.0.0.1:1"))],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("127.0.0.1 port 1"), "{stderr}");
+}
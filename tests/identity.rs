@@ -0,0 +1,60 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs `wt` from the specified directory as a user who has never configured git
+fn wt_without_identity(dir: &Path, home: &Path, args: &[&str]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_wt"));
+    cmd.current_dir(dir)
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env_remove("WT_PROJECTS_DIR")
+        .env_remove("WT_DEFAULT_BRANCH");
+    for var in [
+        "GIT_AUTHOR_NAME",
+        "GIT_AUTHOR_EMAIL",
+        "GIT_COMMITTER_NAME",
+        "GIT_COMMITTER_EMAIL",
+        "EMAIL",
+    ] {
+        cmd.env_remove(var);
+    }
+    cmd.output().unwrap()
+}
+
+#[test]
+fn init_explains_missing_identity() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let home = temp_dir.path().join("home");
+    std::fs::create_dir(&home).unwrap();
+    let output = wt_without_identity(temp_dir.path(), &home, &["init", "proj"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("user.email"), "{stderr}");
+    assert!(stderr.contains("--author"), "{stderr}");
+    // Nothing is left behind to stop the same project being made with --author
+    assert!(!temp_dir.path().join("proj").exists());
+
+    let output = wt_without_identity(
+        temp_dir.path(),
+        &home,
+        &["init", "proj", "--author", "Jane Doe <jane@example.com>"],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let main_wt = String::from_utf8(output.stdout).unwrap();
+    let log = Command::new("git")
+        .arg("-C")
+        .arg(main_wt.trim())
+        .args(["log", "-1", "--format=%an <%ae>|%cn <%ce>"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&log.stdout).trim(),
+        "Jane Doe <jane@example.com>|Jane Doe <jane@example.com>"
+    );
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs `wt` from the specified directory with stdin closed
+fn wt(dir: impl AsRef<Path>, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wt"))
+        .current_dir(dir)
+        .args(args)
+        .env_remove("WT_PROJECTS_DIR")
+        .env_remove("WT_DEFAULT_BRANCH")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap()
+}
+
+/// Creates a project with a single extra worktree and returns the path to its main worktree
+fn init_project(parent: &Path) -> PathBuf {
+    let output = wt(parent, &["init", "proj"]);
+    assert!(output.status.success());
+    let main_wt = PathBuf::from(String::from_utf8(output.stdout).unwrap().trim());
+    assert!(wt(&main_wt, &["new", "feature"]).status.success());
+    main_wt
+}
+
+#[test]
+fn rm_without_names_explains_selection_is_impossible() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    let output = wt(&main_wt, &["rm"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("stdin isn't a terminal"), "{stderr}");
+    assert!(stderr.contains("--all"), "{stderr}");
+}
+
+#[test]
+fn rm_confirmation_requires_yes() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    let output = wt(&main_wt, &["rm", "feature"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("pass --yes"), "{stderr}");
+    assert!(temp_dir.path().join("proj").join("feature").exists());
+
+    let output = wt(&main_wt, &["--yes", "rm", "feature"]);
+    assert!(output.status.success());
+    assert!(!temp_dir.path().join("proj").join("feature").exists());
+}
+
+#[test]
+fn rm_porcelain_prints_removed_paths() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    let feature = temp_dir.path().join("proj").join("feature");
+    let expected = format!("{}\n", feature.display());
+
+    let output = wt(&main_wt, &["rm", "--porcelain", "--dry-run", "feature"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("would remove worktree 'feature'"),
+        "{stderr}"
+    );
+    assert!(feature.exists());
+
+    let output = wt(&main_wt, &["rm", "--porcelain", "--force", "feature"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    assert!(!feature.exists());
+}
+
+#[test]
+fn new_points_at_worktree_with_branch_checked_out() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    let output = wt(&main_wt, &["new", "other", "-b", "feature"]);
+    assert_eq!(output.status.code(), Some(6));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let existing = temp_dir.path().join("proj").join("feature");
+    assert!(stderr.contains(&existing.display().to_string()), "{stderr}");
+    assert!(!temp_dir.path().join("proj").join("other").exists());
+}
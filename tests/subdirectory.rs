@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Runs `wt` from the specified directory, returning stdout
+fn wt(dir: impl AsRef<Path>, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_wt"))
+        .current_dir(dir)
+        .args(args)
+        .env_remove("WT_PROJECTS_DIR")
+        .env_remove("WT_DEFAULT_BRANCH")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "wt {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Creates a project and returns the path to its main worktree
+fn init_project(parent: &Path) -> PathBuf {
+    let main_wt = wt(parent, &["init", "proj"]);
+    PathBuf::from(main_wt.trim())
+}
+
+#[test]
+fn new_from_nested_dir_in_main_worktree() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    let nested = main_wt.join("a").join("b");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    let new_wt = wt(&nested, &["new", "feature"]);
+    assert_eq!(
+        PathBuf::from(new_wt.trim()),
+        temp_dir.path().join("proj").join("feature")
+    );
+    assert!(temp_dir.path().join("proj").join("feature").exists());
+}
+
+#[test]
+fn list_from_nested_dir_in_other_worktree() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    wt(&main_wt, &["new", "feature"]);
+    wt(&main_wt, &["new", "other"]);
+    let nested = temp_dir.path().join("proj").join("feature").join("x");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    let listing = wt(&nested, &["ls"]);
+    assert_eq!(
+        listing.lines().collect::<Vec<_>>(),
+        vec!["* feature", "  other"]
+    );
+}
+
+#[test]
+fn remove_from_nested_dir() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    wt(&main_wt, &["new", "feature"]);
+    wt(&main_wt, &["new", "other"]);
+    let nested = temp_dir
+        .path()
+        .join("proj")
+        .join("other")
+        .join("x")
+        .join("y");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    wt(&nested, &["rm", "--force", "feature"]);
+    assert!(!temp_dir.path().join("proj").join("feature").exists());
+    let listing = wt(&main_wt, &["ls"]);
+    assert_eq!(listing.lines().collect::<Vec<_>>(), vec!["  other"]);
+}
+
+#[test]
+fn repo_option_from_outside_project() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = init_project(temp_dir.path());
+    let outside = tempfile::tempdir().unwrap();
+    let repo = main_wt.to_string_lossy();
+
+    wt(outside.path(), &["--repo", &repo, "new", "feature"]);
+    let listing = wt(outside.path(), &["--repo", &repo, "ls"]);
+    assert_eq!(listing.lines().collect::<Vec<_>>(), vec!["  feature"]);
+    wt(
+        outside.path(),
+        &["--repo", &repo, "rm", "--force", "feature"],
+    );
+    assert!(!temp_dir.path().join("proj").join("feature").exists());
+}
+
+#[test]
+fn repo_option_outside_git_repo_fails() {
+    let outside = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wt"))
+        .args(["--repo"])
+        .arg(outside.path())
+        .arg("ls")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("isn't inside a git repository"));
+}
+
+#[test]
+fn init_with_relative_path() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(temp_dir.path().join("projects")).unwrap();
+    let main_wt = wt(temp_dir.path(), &["init", "--path", "projects", "proj"]);
+    assert!(
+        PathBuf::from(main_wt.trim()).starts_with(temp_dir.path().join("projects").join("proj"))
+    );
+}
+
+#[test]
+fn init_under_projects_dir() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let projects = temp_dir.path().join("projects");
+    let elsewhere = temp_dir.path().join("elsewhere");
+    std::fs::create_dir(&projects).unwrap();
+    std::fs::create_dir(&elsewhere).unwrap();
+    let init = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_wt"))
+            .current_dir(&elsewhere)
+            .args(args)
+            .env("WT_PROJECTS_DIR", &projects)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        PathBuf::from(String::from_utf8(output.stdout).unwrap().trim())
+    };
+    assert!(init(&["init", "proj"]).starts_with(projects.join("proj")));
+    // An explicit path still wins
+    assert!(init(&["init", "--path", ".", "proj"]).starts_with(elsewhere.join("proj")));
+}
+
+#[test]
+fn init_with_fallback_default_branch() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let empty_config = temp_dir.path().join("gitconfig");
+    std::fs::write(&empty_config, "").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wt"))
+        .current_dir(temp_dir.path())
+        .args(["init", "proj"])
+        .env("GIT_CONFIG_GLOBAL", &empty_config)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .env("WT_DEFAULT_BRANCH", "trunk")
+        // The empty global config has no identity to make the initial commit as
+        .env("GIT_AUTHOR_NAME", "Jane Doe")
+        .env("GIT_AUTHOR_EMAIL", "jane@example.com")
+        .env("GIT_COMMITTER_NAME", "Jane Doe")
+        .env("GIT_COMMITTER_EMAIL", "jane@example.com")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        PathBuf::from(String::from_utf8(output.stdout).unwrap().trim()),
+        temp_dir.path().join("proj").join("trunk")
+    );
+}
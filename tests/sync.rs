@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs `wt` from the specified directory
+fn wt(dir: impl AsRef<Path>, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wt"))
+        .current_dir(dir)
+        .args(args)
+        .env_remove("WT_PROJECTS_DIR")
+        .env_remove("WT_DEFAULT_BRANCH")
+        .output()
+        .unwrap()
+}
+
+/// Runs git from the specified directory, panicking if it fails
+fn git(dir: impl AsRef<Path>, args: &[&str]) {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+}
+
+/// Clones a repository with a single commit into a project and returns its main worktree
+fn cloned_project(parent: &Path) -> PathBuf {
+    let upstream = parent.join("upstream");
+    git(parent, &["init", "-q", "upstream"]);
+    git(
+        &upstream,
+        &["commit", "-q", "--allow-empty", "-m", "initial"],
+    );
+    let url = format!("file://{}", upstream.display());
+    let clones = parent.join("clones");
+    std::fs::create_dir(&clones).unwrap();
+    let output = wt(&clones, &["clone", &url]);
+    assert!(output.status.success(), "{output:?}");
+    PathBuf::from(String::from_utf8(output.stdout).unwrap().trim())
+}
+
+#[test]
+fn sync_warns_about_dirty_worktrees() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let main_wt = cloned_project(temp_dir.path());
+    let default_branch = main_wt.file_name().unwrap().to_string_lossy();
+    assert!(wt(&main_wt, &["new", "dirty"]).status.success());
+    let dirty = main_wt.parent().unwrap().join("dirty");
+    git(
+        &dirty,
+        &[
+            "branch",
+            "--set-upstream-to",
+            &format!("origin/{default_branch}"),
+        ],
+    );
+    std::fs::write(dirty.join("scratch.txt"), "").unwrap();
+
+    let output = wt(&main_wt, &["sync", "--pull"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("skipped 'dirty' because it has uncommitted changes"),
+        "{stderr}"
+    );
+
+    let output = wt(&main_wt, &["--quiet", "sync", "--pull"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(output.stderr.is_empty(), "{output:?}");
+}